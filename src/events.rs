@@ -0,0 +1,208 @@
+use crate::{GameId, GameResult, Move, Player, Standing, TimeStamp, Variant};
+
+use soroban_sdk::{symbol, Address, BytesN, Env, Vec};
+
+// every event is published under the ("rps", <step>) topic pair
+
+pub(crate) fn initialized(
+    e: &Env,
+    token: BytesN<32>,
+    min_bet: i128,
+    max_bet: i128,
+    ts_limit: TimeStamp,
+    join_limit: TimeStamp,
+) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("init")),
+        (token, min_bet, max_bet, ts_limit, join_limit),
+    );
+}
+
+pub(crate) fn created(
+    e: &Env,
+    game: GameId,
+    creator: Address,
+    token: BytesN<32>,
+    bet: i128,
+    variant: Variant,
+    rounds: u32,
+) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("created")),
+        (game, creator, token, bet, variant, rounds),
+    );
+}
+
+pub(crate) fn joined(e: &Env, game: GameId, user: Address, player: Player, bet: i128) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("joined")),
+        (game, user, player, bet),
+    );
+}
+
+// a player of a series committed to the move of `round`, counted from 1
+pub(crate) fn committed(e: &Env, game: GameId, user: Address, player: Player, round: u32) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("committed")),
+        (game, user, player, round),
+    );
+}
+
+pub(crate) fn revealed(e: &Env, game: GameId, user: Address, player: Player, user_move: Move) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("revealed")),
+        (game, user, player, user_move),
+    );
+}
+
+pub(crate) fn round(e: &Env, game: GameId, round: u32, result: GameResult) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("round")), (game, round, result));
+}
+
+// `payout` is the amount sent to the winner, or to each player on a draw
+pub(crate) fn settled(e: &Env, game: GameId, result: GameResult, payout: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("settled")), (game, result, payout));
+}
+
+pub(crate) fn cancelled(e: &Env, game: GameId, to: Address, payout: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("cancelled")), (game, to, payout));
+}
+
+pub(crate) fn room_created(
+    e: &Env,
+    room: GameId,
+    creator: Address,
+    token: BytesN<32>,
+    bet: i128,
+    seats: u32,
+) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("room_new")),
+        (room, creator, token, bet, seats),
+    );
+}
+
+pub(crate) fn room_joined(e: &Env, room: GameId, user: Address, seat: u32, bet: i128) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("room_join")),
+        (room, user, seat, bet),
+    );
+}
+
+pub(crate) fn room_revealed(e: &Env, room: GameId, user: Address, seat: u32, user_move: Move) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("room_rev")),
+        (room, user, seat, user_move),
+    );
+}
+
+// `share` is the amount sent to each winner
+pub(crate) fn room_won(e: &Env, room: GameId, winners: Vec<Address>, share: i128) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("room_won")),
+        (room, winners, share),
+    );
+}
+
+pub(crate) fn tourn_created(
+    e: &Env,
+    tourn: GameId,
+    creator: Address,
+    token: BytesN<32>,
+    fee: i128,
+    size: u32,
+) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("tourn_new")),
+        (tourn, creator, token, fee, size),
+    );
+}
+
+pub(crate) fn tourn_joined(e: &Env, tourn: GameId, user: Address, seed: u32) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("tourn_join")), (tourn, user, seed));
+}
+
+// `matches` are the games that were just paired: the whole first round, then every later game
+// as soon as both its players are known
+pub(crate) fn tourn_round(e: &Env, tourn: GameId, matches: Vec<GameId>) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("tourn_rnd")), (tourn, matches));
+}
+
+pub(crate) fn tourn_won(e: &Env, tourn: GameId, ranking: Vec<Address>) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("tourn_won")), (tourn, ranking));
+}
+
+pub(crate) fn season_created(
+    e: &Env,
+    season: u32,
+    token: BytesN<32>,
+    start: TimeStamp,
+    end: TimeStamp,
+) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("season_new")),
+        (season, token, start, end),
+    );
+}
+
+pub(crate) fn pool_funded(e: &Env, season: u32, amount: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("pool_add")), (season, amount));
+}
+
+// `board` is the final leaderboard, `pool` what was paid out of it
+pub(crate) fn season_ended(e: &Env, season: u32, board: Vec<Standing>, pool: i128) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("season_end")),
+        (season, board, pool),
+    );
+}
+
+pub(crate) fn queued(e: &Env, user: Address, token: BytesN<32>, stake: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("queued")), (user, token, stake));
+}
+
+// `payout` is the stake refunded, house fee taken
+pub(crate) fn unqueued(e: &Env, user: Address, token: BytesN<32>, payout: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("unqueued")), (user, token, payout));
+}
+
+pub(crate) fn fees_claimed(e: &Env, token: BytesN<32>, to: Address, amount: i128) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("fees")), (token, to, amount));
+}
+
+pub(crate) fn token_added(e: &Env, token: BytesN<32>, min_bet: i128, max_bet: i128) {
+    e.events().publish(
+        (symbol!("rps"), symbol!("token_add")),
+        (token, min_bet, max_bet),
+    );
+}
+
+pub(crate) fn token_removed(e: &Env, token: BytesN<32>) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("token_rm")), token);
+}
+
+pub(crate) fn limit_set(e: &Env, ts_limit: TimeStamp) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("limit")), ts_limit);
+}
+
+pub(crate) fn paused(e: &Env, paused: bool) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("paused")), paused);
+}
+
+pub(crate) fn admin_set(e: &Env, admin: Address) {
+    e.events()
+        .publish((symbol!("rps"), symbol!("admin")), admin);
+}
@@ -1,23 +1,31 @@
 #![no_std]
 
+mod events;
+mod ruleset;
+mod season;
 mod test;
+mod tournament;
+
+pub use ruleset::Ruleset;
+pub use season::{Season, Standing};
+pub use tournament::Tournament;
 
 use soroban_sdk::{
-    bytes, contracterror, contractimpl, contracttype, panic_with_error, serde::Serialize, symbol,
-    Address, Bytes, BytesN, Env,
+    bytes, contracterror, contractimpl, contracttype, panic_with_error, serde::Serialize, Address,
+    Bytes, BytesN, Env, Vec,
 };
 
 mod token {
     soroban_sdk::contractimport!(file = "./soroban_token_spec.wasm");
 }
 
-fn check_player(e: &Env, player: Player) -> bool {
-    let key = DataKey::Player(player);
+fn check_player(e: &Env, game: GameId, player: Player) -> bool {
+    let key = DataKey::Player(game, player);
     e.storage().has(&key)
 }
 
-fn check_revealed(e: &Env, player: Player) -> bool {
-    let obj = get_move(e, player);
+fn check_revealed(e: &Env, game: GameId, player: Player) -> bool {
+    let obj = get_move(e, game, player);
 
     !matches!(obj.move_pre, Move::Unrevealed)
 }
@@ -32,37 +40,161 @@ fn game_started(e: &Env) -> bool {
     e.storage().get(&key).unwrap_or(Ok(false)).unwrap()
 }
 
-fn remove_player(e: &Env, player: Player) {
-    let key = DataKey::Player(player);
+fn next_game_id(e: &Env) -> GameId {
+    let key = DataKey::GameCount;
+    let id: u64 = e.storage().get(&key).unwrap_or(Ok(0)).unwrap();
+    e.storage().set(&key, &(id + 1));
+    GameId(id)
+}
+
+fn put_phase(e: &Env, game: GameId, phase: GamePhase) {
+    let key = DataKey::Phase(game);
+    e.storage().set(&key, &phase);
+}
+
+fn get_phase(e: &Env, game: GameId) -> Result<GamePhase, Error> {
+    let key = DataKey::Phase(game);
+    match e.storage().get(&key) {
+        Some(phase) => Ok(phase.unwrap()),
+        None => Err(Error::NoSuchGame),
+    }
+}
+
+// errors unless the game is in one of the `expected` phases, returns the current phase otherwise
+fn require_phase(e: &Env, game: GameId, expected: &[GamePhase]) -> Result<GamePhase, Error> {
+    let phase = get_phase(e, game)?;
+    if expected.contains(&phase) {
+        Ok(phase)
+    } else {
+        Err(Error::WrongPhase)
+    }
+}
+
+// archives a finished game, `paid_one` and `paid_two` being what each player was sent, then wipes
+// its players. The phase entry stays so that the id can't be played again
+fn close_game(e: &Env, game: GameId, phase: GamePhase, paid_one: i128, paid_two: i128) {
+    let players = get_players(e, game);
+    for obj in players.iter() {
+        add_to_history(e, obj.unwrap().id, game);
+    }
+    put_history(
+        e,
+        game,
+        &History {
+            game,
+            phase,
+            token: get_game_token(e, game),
+            bet: get_game_bet(e, game),
+            players,
+            results: get_results(e, game),
+            paid_one,
+            paid_two,
+            bet_start: get_bet_start_opt(e, game).unwrap_or(TimeStamp(0)),
+            ended: TimeStamp::current(e),
+        },
+    );
+
+    remove_player(e, game, Player::One);
+    remove_player(e, game, Player::Two);
+    e.storage().remove(&DataKey::Next(game, Player::One));
+    e.storage().remove(&DataKey::Next(game, Player::Two));
+    e.storage().remove(&DataKey::BetStart(game));
+    put_phase(e, game, phase);
+}
+
+fn put_history(e: &Env, game: GameId, history: &History) {
+    let key = DataKey::History(game);
+    e.storage().set(&key, history);
+}
+
+fn get_history(e: &Env, game: GameId) -> History {
+    let key = DataKey::History(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::NoSuchGame))
+        .unwrap()
+}
+
+// number of finished games `user` played in
+fn get_history_len(e: &Env, user: Address) -> u32 {
+    let key = DataKey::HistLen(user);
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
+
+// the finished games of each player are indexed in the order they ended
+fn add_to_history(e: &Env, user: Address, game: GameId) {
+    let len = get_history_len(e, user.clone());
+    e.storage()
+        .set(&DataKey::HistGame(user.clone(), len), &game);
+    e.storage().set(&DataKey::HistLen(user), &(len + 1));
+}
+
+fn remove_player(e: &Env, game: GameId, player: Player) {
+    let key = DataKey::Player(game, player);
     e.storage().remove(&key);
 }
 
-fn store_move(e: &Env, player: Player, val: PlayerObj) {
-    let key = DataKey::Player(player);
+fn store_move(e: &Env, game: GameId, player: Player, val: PlayerObj) {
+    let key = DataKey::Player(game, player);
     e.storage().set(&key, &val);
 }
 
-fn get_move(e: &Env, player: Player) -> PlayerObj {
-    let key = DataKey::Player(player);
+fn get_player_opt(e: &Env, game: GameId, player: Player) -> Option<PlayerObj> {
+    let key = DataKey::Player(game, player);
+    e.storage().get(&key).map(|obj| obj.unwrap())
+}
+
+// the players who joined `game` so far, player one first
+fn get_players(e: &Env, game: GameId) -> Vec<PlayerObj> {
+    let mut players = Vec::new(e);
+    for player in [Player::One, Player::Two] {
+        if let Some(obj) = get_player_opt(e, game, player) {
+            players.push_back(obj);
+        }
+    }
+    players
+}
+
+fn get_move(e: &Env, game: GameId, player: Player) -> PlayerObj {
+    let key = DataKey::Player(game, player);
     e.storage()
         .get(&key)
         .unwrap_or_else(|| panic_with_error!(e, Error::InvalidOp))
         .unwrap()
 }
 
-fn put_token(e: &Env, token: BytesN<32>) {
-    let key = DataKey::Token;
-    e.storage().set(&key, &token);
-}
-
-fn get_token(e: &Env) -> BytesN<32> {
-    let key = DataKey::Token;
+fn get_tokens(e: &Env) -> Vec<BytesN<32>> {
+    let key = DataKey::Tokens;
     e.storage()
         .get(&key)
-        .unwrap_or_else(|| panic_with_error!(e, Error::GameNotStarted))
+        .unwrap_or_else(|| Ok(Vec::new(e)))
         .unwrap()
 }
 
+// adds `token` to the allowlist, or updates its bounds if it's already there
+fn put_token(e: &Env, token: BytesN<32>, bounds: BetBounds) {
+    let mut tokens = get_tokens(e);
+    if !tokens.contains(&token) {
+        tokens.push_back(token.clone());
+        e.storage().set(&DataKey::Tokens, &tokens);
+    }
+    e.storage().set(&DataKey::Bounds(token), &bounds);
+}
+
+fn remove_token(e: &Env, token: BytesN<32>) {
+    let mut tokens = get_tokens(e);
+    if let Some(i) = tokens.first_index_of(&token) {
+        tokens.remove(i);
+        e.storage().set(&DataKey::Tokens, &tokens);
+    }
+    e.storage().remove(&DataKey::Bounds(token));
+}
+
+fn get_bounds(e: &Env, token: BytesN<32>) -> Option<BetBounds> {
+    let key = DataKey::Bounds(token);
+    e.storage().get(&key).map(|bounds| bounds.unwrap())
+}
+
 fn put_ts_limit(e: &Env, ts_diff: TimeStamp) {
     let key = DataKey::TsLimit;
     e.storage().set(&key, &ts_diff);
@@ -76,291 +208,2361 @@ fn get_ts_limit(e: &Env) -> TimeStamp {
         .unwrap()
 }
 
-fn put_bet_start(e: &Env, ts: TimeStamp) {
-    let key = DataKey::BetStart;
-    e.storage().set(&key, &ts);
+fn put_join_limit(e: &Env, join_diff: TimeStamp) {
+    let key = DataKey::JoinLimit;
+    e.storage().set(&key, &join_diff);
+}
+
+fn get_join_limit(e: &Env) -> TimeStamp {
+    let key = DataKey::JoinLimit;
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::GameNotStarted))
+        .unwrap()
+}
+
+fn put_treasury(e: &Env, treasury: Address) {
+    let key = DataKey::Treasury;
+    e.storage().set(&key, &treasury);
+}
+
+fn get_treasury(e: &Env) -> Option<Address> {
+    let key = DataKey::Treasury;
+    e.storage().get(&key).map(|treasury| treasury.unwrap())
+}
+
+fn put_fee_bps(e: &Env, fee_bps: u32) {
+    let key = DataKey::FeeBps;
+    e.storage().set(&key, &fee_bps);
+}
+
+fn get_fee_bps(e: &Env) -> u32 {
+    let key = DataKey::FeeBps;
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
+
+fn put_fee_to(e: &Env, fee_to: Address) {
+    let key = DataKey::FeeTo;
+    e.storage().set(&key, &fee_to);
 }
 
-fn get_bet_start(e: &Env) -> TimeStamp {
-    let key = DataKey::BetStart;
+fn get_fee_to(e: &Env) -> Address {
+    let key = DataKey::FeeTo;
     e.storage()
         .get(&key)
         .unwrap_or_else(|| panic_with_error!(e, Error::GameNotStarted))
         .unwrap()
 }
 
-fn put_bet(e: &Env, amount: i128) {
-    let key = DataKey::BetAmount;
+fn put_fee_balance(e: &Env, token: BytesN<32>, amount: i128) {
+    let key = DataKey::Fees(token);
     e.storage().set(&key, &amount);
 }
 
-fn get_bet(e: &Env) -> i128 {
-    let key = DataKey::BetAmount;
+fn get_fee_balance(e: &Env, token: BytesN<32>) -> i128 {
+    let key = DataKey::Fees(token);
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
+
+fn put_admin(e: &Env, admin: Address) {
+    let key = DataKey::Admin;
+    e.storage().set(&key, &admin);
+}
+
+fn get_admin(e: &Env) -> Address {
+    let key = DataKey::Admin;
     e.storage()
         .get(&key)
         .unwrap_or_else(|| panic_with_error!(e, Error::GameNotStarted))
         .unwrap()
 }
 
-fn place_bet(e: &Env, from: Address) {
-    let client = token::Client::new(e, &get_token(e));
-    client.xfer_from(
-        &e.current_contract_address(),
-        &from,
-        &e.current_contract_address(),
-        &get_bet(e),
-    );
+fn put_paused(e: &Env, paused: bool) {
+    let key = DataKey::Paused;
+    e.storage().set(&key, &paused);
 }
 
-fn send_profit(e: &Env, to: Address, amount: i128) {
-    let client = token::Client::new(e, &get_token(e));
-    client.xfer(&e.current_contract_address(), &to, &amount)
+fn is_paused(e: &Env) -> bool {
+    let key = DataKey::Paused;
+    e.storage().get(&key).unwrap_or(Ok(false)).unwrap()
 }
 
-// Perform arithmetic ops on custom types
-trait Arithmetic<Rhs = Self> {
-    type Output;
+// commitments are registered per player, so that copying somebody else's pending commitment
+// doesn't use it up for them. Entries are never removed: every commitment sent costs one ledger
+// entry for good
+fn put_commitment_used(e: &Env, user: Address, user_move: BytesN<32>) {
+    let key = DataKey::Commit(user, user_move);
+    e.storage().set(&key, &true);
+}
 
-    fn add(self, rhs: Rhs) -> Self::Output;
+fn commitment_used(e: &Env, user: Address, user_move: BytesN<32>) -> bool {
+    let key = DataKey::Commit(user, user_move);
+    e.storage().has(&key)
+}
 
-    fn sub(self, rhs: Rhs) -> Self::Output;
+// the player waiting in the matchmaking queue for an opponent at `stake` in `token`. There's
+// never more than one, since the next player to join is paired with them
+fn put_queue(e: &Env, token: BytesN<32>, stake: i128, ticket: &Ticket) {
+    let key = DataKey::Queue(token, stake);
+    e.storage().set(&key, ticket);
 }
 
-#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug)]
-#[contracttype]
-/// Timestamp type to enforce explicitness
-pub struct TimeStamp(pub u64);
+fn get_queue(e: &Env, token: BytesN<32>, stake: i128) -> Option<Ticket> {
+    let key = DataKey::Queue(token, stake);
+    e.storage().get(&key).map(|ticket| ticket.unwrap())
+}
 
-impl TimeStamp {
-    fn current(e: &Env) -> Self {
-        Self(e.ledger().timestamp())
-    }
+fn remove_queue(e: &Env, token: BytesN<32>, stake: i128) {
+    e.storage().remove(&DataKey::Queue(token, stake));
 }
 
-impl Arithmetic<TimeStamp> for TimeStamp {
-    type Output = TimeStamp;
+fn put_queued(e: &Env, game: GameId) {
+    let key = DataKey::Queued(game);
+    e.storage().set(&key, &true);
+}
 
-    fn add(self, other: Self) -> Self {
-        Self(self.0 + other.0)
+// the game id the commitments to `game` are bound to
+fn commit_game(e: &Env, game: GameId) -> GameId {
+    if e.storage().has(&DataKey::Queued(game)) {
+        QUEUE_GAME
+    } else {
+        game
     }
+}
 
-    fn sub(self, other: Self) -> Self {
-        Self(self.0 - other.0)
-    }
+fn put_creator(e: &Env, game: GameId, creator: Address) {
+    let key = DataKey::Creator(game);
+    e.storage().set(&key, &creator);
 }
 
-#[contracterror]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
-#[repr(u32)]
-pub enum Error {
-    GameNotStarted = 1,
-    MaxPlayersHit = 2,
-    InvalidReveal = 3,
-    InvalidOp = 4,
-    NotRevealed = 5,
-    LimitNotReached = 6,
-    InvalidSignature = 7,
+fn get_creator(e: &Env, game: GameId) -> Address {
+    let key = DataKey::Creator(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::NoSuchGame))
+        .unwrap()
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub enum Player {
-    One,
-    Two,
+fn put_allow(e: &Env, game: GameId, allow: Vec<Address>) {
+    let key = DataKey::Allow(game);
+    e.storage().set(&key, &allow);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub enum GameResult {
-    Winner(Player),
-    Draw,
+fn get_allow(e: &Env, game: GameId) -> Vec<Address> {
+    let key = DataKey::Allow(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
 }
 
-#[contracttype]
-#[derive(Clone, Copy)]
-#[repr(u32)]
-pub enum Move {
-    Rock = 0,
-    Paper = 1,
-    Scissors = 2,
-    Unrevealed = 3,
+fn put_deny(e: &Env, game: GameId, deny: Vec<Address>) {
+    let key = DataKey::Deny(game);
+    e.storage().set(&key, &deny);
 }
 
-impl Move {
-    pub fn as_bytes(&self, env: &Env) -> Bytes {
-        match self {
-            Move::Rock => bytes!(env, 0x526f636b),
-            Move::Paper => bytes!(env, 0x5061706572),
-            Move::Scissors => bytes!(env, 0x53636973736f7273),
-            _ => panic_with_error!(env, Error::InvalidOp),
-        }
-    }
+fn get_deny(e: &Env, game: GameId) -> Vec<Address> {
+    let key = DataKey::Deny(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
 
-    pub fn repr(&self) -> u32 {
-        *self as u32
-    }
+fn put_opponent(e: &Env, game: GameId, opponent: Address) {
+    let key = DataKey::Opponent(game);
+    e.storage().set(&key, &opponent);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub struct PlayerObj {
-    id: Address,
-    user_move: BytesN<32>,
-    move_pre: Move,
+fn get_opponent(e: &Env, game: GameId) -> Option<Address> {
+    let key = DataKey::Opponent(game);
+    e.storage().get(&key).map(|opponent| opponent.unwrap())
 }
 
-impl PlayerObj {
-    pub fn new(id: Address, user_move: BytesN<32>) -> Self {
-        PlayerObj {
-            id,
-            user_move,
-            move_pre: Move::Unrevealed,
+fn can_join(e: &Env, game: GameId, user: &Address) -> bool {
+    // private games reserve the first slot to the creator and the second one to the opponent
+    if let Some(opponent) = get_opponent(e, game) {
+        let reserved = if check_player(e, game, Player::One) {
+            opponent
+        } else {
+            get_creator(e, game)
+        };
+
+        if *user != reserved {
+            return false;
         }
     }
+
+    let allow = get_allow(e, game);
+    (allow.is_empty() || allow.contains(user)) && !get_deny(e, game).contains(user)
 }
 
-#[contracttype]
-#[derive(Clone)]
-/// Contract data keys
-pub enum DataKey {
-    BetStart,
-    TsLimit,
-    Started,
-    Token,
-    BetAmount,
-    Player(Player),
+fn limit_reached(e: &Env, since: TimeStamp, limit: TimeStamp) -> bool {
+    TimeStamp::current(e) >= since.add(limit)
 }
 
-/// Contract trait
-pub trait RockPaperScissorsTrait {
-    // leaving this one for possible updates in the future that need a contract initialization
-    fn initialize(
-        e: Env,
-        token: BytesN<32>,
-        bet_amount: i128,
-        ts_diff: TimeStamp,
-    ) -> Result<(), Error>;
+// the bet start is the time of the latest commitment: player one's while the game is open,
+// player two's once both have committed
+fn put_bet_start(e: &Env, game: GameId, ts: TimeStamp) {
+    let key = DataKey::BetStart(game);
+    e.storage().set(&key, &ts);
+}
 
-    fn make_move(e: Env, user: Address, user_move: BytesN<32>) -> Result<(), Error>;
+fn get_bet_start(e: &Env, game: GameId) -> TimeStamp {
+    let key = DataKey::BetStart(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::GameNotStarted))
+        .unwrap()
+}
 
-    fn reveal(e: Env, player: Player, user_move: Move, secret: Bytes) -> Result<Move, Error>;
+fn get_bet_start_opt(e: &Env, game: GameId) -> Option<TimeStamp> {
+    let key = DataKey::BetStart(game);
+    e.storage().get(&key).map(|ts| ts.unwrap())
+}
 
-    fn evaluate(e: Env) -> Result<GameResult, Error>;
+fn valid_bounds(min_bet: i128, max_bet: i128) -> bool {
+    min_bet > 0 && min_bet <= max_bet
+}
 
-    fn cancel(e: Env) -> Result<(), Error>;
+// each game's token is picked by its creator from the allowlist
+fn put_game_token(e: &Env, game: GameId, token: BytesN<32>) {
+    let key = DataKey::GameToken(game);
+    e.storage().set(&key, &token);
 }
 
-pub struct RockPaperScissorsContract;
+fn get_game_token(e: &Env, game: GameId) -> BytesN<32> {
+    let key = DataKey::GameToken(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::NoSuchGame))
+        .unwrap()
+}
 
-#[contractimpl]
-impl RockPaperScissorsTrait for RockPaperScissorsContract {
-    fn initialize(
-        e: Env,
-        token: BytesN<32>,
-        bet_amount: i128,
-        ts_diff: TimeStamp,
-    ) -> Result<(), Error> {
-        if !game_started(&e) {
-            put_started(&e, true);
-            put_token(&e, token);
-            put_bet(&e, bet_amount);
-            put_ts_limit(&e, ts_diff);
-            Ok(())
-        } else {
-            Err(Error::GameNotStarted)
-        }
-    }
+// each game's bet is picked by its creator within the token's bounds
+fn put_game_bet(e: &Env, game: GameId, amount: i128) {
+    let key = DataKey::GameBet(game);
+    e.storage().set(&key, &amount);
+}
 
-    fn make_move(e: Env, user: Address, user_move: BytesN<32>) -> Result<(), Error> {
-        if !game_started(&e) {
-            panic!("game started yet")
-        }
+fn get_game_bet(e: &Env, game: GameId) -> i128 {
+    let key = DataKey::GameBet(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, Error::NoSuchGame))
+        .unwrap()
+}
 
-        user.require_auth();
+// games created before series were introduced don't have the entry and are single rounds
+fn put_rounds(e: &Env, game: GameId, rounds: u32) {
+    let key = DataKey::Rounds(game);
+    e.storage().set(&key, &rounds);
+}
 
-        let player_obj = PlayerObj::new(user.clone(), user_move);
+fn get_rounds(e: &Env, game: GameId) -> u32 {
+    let key = DataKey::Rounds(game);
+    e.storage().get(&key).unwrap_or(Ok(1)).unwrap()
+}
 
-        if !check_player(&e, Player::One) {
-            store_move(&e, Player::One, player_obj);
-            place_bet(&e, user);
-            Ok(())
-        } else if !check_player(&e, Player::Two) {
-            store_move(&e, Player::Two, player_obj);
-            place_bet(&e, user);
-            put_bet_start(&e, TimeStamp::current(&e));
-            Ok(())
-        } else {
-            Err(Error::MaxPlayersHit)
-        }
-    }
+fn put_results(e: &Env, game: GameId, results: Vec<RoundResult>) {
+    let key = DataKey::Results(game);
+    e.storage().set(&key, &results);
+}
 
-    // doesn't need authenticating since the revealer needs to know the secret
-    // the account id for the hash is only needed so that the hash image doesn't coincide if the same moves are hashed with the same secrets by two different users
-    fn reveal(e: Env, player: Player, user_move: Move, secret: Bytes) -> Result<Move, Error> {
-        let mut player_obj = get_move(&e, player.clone());
+fn get_results(e: &Env, game: GameId) -> Vec<RoundResult> {
+    let key = DataKey::Results(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| Ok(Vec::new(e)))
+        .unwrap()
+}
 
-        let mut rhs = Bytes::new(&e);
-        rhs.append(&player_obj.clone().id.serialize(&e));
-        rhs.append(&user_move.as_bytes(&e));
-        rhs.append(&secret);
+// how many drawn rounds can be replayed, games created before sudden death was introduced
+// don't have the entry and replay none
+fn put_replays(e: &Env, game: GameId, replays: u32) {
+    let key = DataKey::Replays(game);
+    e.storage().set(&key, &replays);
+}
 
-        let rhs_hash = e.crypto().sha256(&rhs);
+fn get_replays(e: &Env, game: GameId) -> u32 {
+    let key = DataKey::Replays(game);
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
 
-        if player_obj.user_move != rhs_hash {
-            return Err(Error::InvalidReveal);
-        }
+fn put_replayed(e: &Env, game: GameId, replayed: u32) {
+    let key = DataKey::Replayed(game);
+    e.storage().set(&key, &replayed);
+}
 
-        player_obj.move_pre = user_move;
-        store_move(&e, player, player_obj);
-        Ok(user_move)
-    }
+fn get_replayed(e: &Env, game: GameId) -> u32 {
+    let key = DataKey::Replayed(game);
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
 
-    fn evaluate(e: Env) -> Result<GameResult, Error> {
-        // check that both players have revealed
-        if !check_revealed(&e, Player::One) || !check_revealed(&e, Player::Two) {
-            return Err(Error::NotRevealed);
-        }
+// commitments to the next round are held here until both players have sent theirs, so that
+// the previous round's moves stay readable in the meantime
+fn put_next(e: &Env, game: GameId, player: Player, user_move: BytesN<32>) {
+    let key = DataKey::Next(game, player);
+    e.storage().set(&key, &user_move);
+}
 
-        let p1_obj = get_move(&e, Player::One);
-        let p2_obj = get_move(&e, Player::Two);
-
-        if (p1_obj.move_pre.repr() + 1) % 3 == p2_obj.move_pre.repr() {
-            send_profit(&e, p2_obj.id, get_bet(&e) * 2);
-            remove_player(&e, Player::One);
-            remove_player(&e, Player::Two);
-            Ok(GameResult::Winner(Player::Two))
-        } else if p1_obj.move_pre.repr() == p2_obj.move_pre.repr() {
-            // give back the betted money to both players
-            send_profit(&e, p1_obj.id, get_bet(&e));
-            send_profit(&e, p2_obj.id, get_bet(&e));
-            remove_player(&e, Player::One);
-            remove_player(&e, Player::Two);
-            Ok(GameResult::Draw)
-        } else {
-            send_profit(&e, p1_obj.id, get_bet(&e) * 2);
-            remove_player(&e, Player::One);
-            remove_player(&e, Player::Two);
-            Ok(GameResult::Winner(Player::One))
-        }
+fn get_next(e: &Env, game: GameId, player: Player) -> Option<BytesN<32>> {
+    let key = DataKey::Next(game, player);
+    e.storage().get(&key).map(|user_move| user_move.unwrap())
+}
+
+// moves the pending commitments into the players and opens the reveals of the next round
+fn start_round(e: &Env, game: GameId) {
+    for player in [Player::One, Player::Two] {
+        let mut player_obj = get_move(e, game, player.clone());
+        player_obj.user_move = get_next(e, game, player.clone())
+            .unwrap_or_else(|| panic_with_error!(e, Error::InvalidOp));
+        player_obj.move_pre = Move::Unrevealed;
+        store_move(e, game, player.clone(), player_obj);
+        e.storage().remove(&DataKey::Next(game, player));
     }
+    put_bet_start(e, game, TimeStamp::current(e));
+    put_phase(e, game, GamePhase::Committed);
+}
 
-    fn cancel(e: Env) -> Result<(), Error> {
-        if TimeStamp::current(&e).sub(get_ts_limit(&e)) < get_bet_start(&e) {
-            return Err(Error::LimitNotReached);
-        }
+// games created before variants were introduced don't have the entry and are classic ones
+fn put_variant(e: &Env, game: GameId, variant: Variant) {
+    let key = DataKey::Variant(game);
+    e.storage().set(&key, &variant);
+}
 
-        let p_obj: PlayerObj;
-        if !check_revealed(&e, Player::One) && check_revealed(&e, Player::Two) {
-            p_obj = get_move(&e, Player::Two);
-        } else if check_revealed(&e, Player::One) && !check_revealed(&e, Player::Two) {
-            p_obj = get_move(&e, Player::One);
-        } else {
-            return Err(Error::LimitNotReached);
+fn get_variant(e: &Env, game: GameId) -> Variant {
+    let key = DataKey::Variant(game);
+    e.storage()
+        .get(&key)
+        .unwrap_or(Ok(Variant::Classic))
+        .unwrap()
+}
+
+fn put_ruleset(e: &Env, game: GameId, ruleset: Ruleset) {
+    let key = DataKey::Ruleset(game);
+    e.storage().set(&key, &ruleset);
+}
+
+// games created before rulesets were stored get the one of their variant
+fn get_ruleset(e: &Env, game: GameId) -> Ruleset {
+    let key = DataKey::Ruleset(game);
+    e.storage()
+        .get(&key)
+        .map(|ruleset| ruleset.unwrap())
+        .unwrap_or_else(|| get_variant(e, game).ruleset(e))
+}
+
+// the outcome of the game once it's decided: a side clinched the majority of the rounds, or
+// all rounds were played and the side with more wins takes it. Replayed draws aren't played rounds
+fn series_result(results: &Vec<RoundResult>, rounds: u32, replayed: u32) -> Option<GameResult> {
+    let mut one = 0;
+    let mut two = 0;
+    for round in results.iter() {
+        match round.unwrap().result {
+            GameResult::Winner(Player::One) => one += 1,
+            GameResult::Winner(Player::Two) => two += 1,
+            GameResult::Draw => (),
+        }
+    }
+    let played = results.len() - replayed;
+
+    if one > rounds / 2 || (played >= rounds && one > two) {
+        Some(GameResult::Winner(Player::One))
+    } else if two > rounds / 2 || (played >= rounds && two > one) {
+        Some(GameResult::Winner(Player::Two))
+    } else if played >= rounds {
+        Some(GameResult::Draw)
+    } else {
+        None
+    }
+}
+
+// free games (tournament matches) don't move any funds
+fn place_bet(e: &Env, token: &BytesN<32>, from: Address, amount: i128) {
+    if amount == 0 {
+        return;
+    }
+
+    let client = token::Client::new(e, token);
+    client.xfer_from(
+        &e.current_contract_address(),
+        &from,
+        &e.current_contract_address(),
+        &amount,
+    );
+}
+
+fn send_profit(e: &Env, token: &BytesN<32>, to: Address, amount: i128) {
+    if amount == 0 {
+        return;
+    }
+
+    let client = token::Client::new(e, token);
+    client.xfer(&e.current_contract_address(), &to, &amount)
+}
+
+// credits the house fee on `amount` to the fee balance of `token`, returns what's left to pay out
+fn take_fee(e: &Env, token: &BytesN<32>, amount: i128) -> i128 {
+    let fee = amount * get_fee_bps(e) as i128 / 10_000;
+    put_fee_balance(e, token.clone(), get_fee_balance(e, token.clone()) + fee);
+    amount - fee
+}
+
+// sends `amount` of the game's token minus the house fee to `to`, returns what was actually sent
+fn pay_out(e: &Env, game: GameId, to: Address, amount: i128) -> i128 {
+    let token = get_game_token(e, game);
+    let payout = take_fee(e, &token, amount);
+    send_profit(e, &token, to, payout);
+    payout
+}
+
+// every player of a room stakes the same bet, so splitting the pot proportionally to the stakes
+// splits it evenly. What can't be split evenly goes to the fees, returns each share
+fn split_pot(e: &Env, token: &BytesN<32>, pot: i128, to: &Vec<Address>) -> i128 {
+    let share = pot / to.len() as i128;
+    for addr in to.iter() {
+        send_profit(e, token, addr.unwrap(), share);
+    }
+
+    let dust = pot - share * to.len() as i128;
+    if dust > 0 {
+        put_fee_balance(e, token.clone(), get_fee_balance(e, token.clone()) + dust);
+    }
+    share
+}
+
+// how a settled game or room ended for one of its players
+enum Outcome {
+    Win,
+    Loss,
+    Draw,
+    Forfeit,
+}
+
+fn put_stats(e: &Env, user: Address, stats: &Stats) {
+    let key = DataKey::Stats(user);
+    e.storage().set(&key, stats);
+}
+
+fn get_stats(e: &Env, user: Address) -> Stats {
+    let key = DataKey::Stats(user);
+    e.storage()
+        .get(&key)
+        .unwrap_or(Ok(Stats::default()))
+        .unwrap()
+}
+
+// adds a settled game to the stats of `user`, who put `stake` in and got `payout` back, and to
+// the season running in `token`
+fn record(
+    e: &Env,
+    token: &BytesN<32>,
+    user: &Address,
+    outcome: Outcome,
+    stake: i128,
+    payout: i128,
+) {
+    let mut stats = get_stats(e, user.clone());
+    match outcome {
+        Outcome::Win => stats.wins += 1,
+        Outcome::Loss => stats.losses += 1,
+        Outcome::Draw => stats.draws += 1,
+        Outcome::Forfeit => stats.forfeits += 1,
+    }
+    stats.wagered += stake;
+    stats.net_profit += payout - stake;
+    put_stats(e, user.clone(), &stats);
+    season::add_net(e, token, user, payout - stake);
+}
+
+// rating of an address that never finished a game
+const INITIAL_RATING: i32 = 1500;
+
+// K-factor of contracts initialized before ratings existed
+const DEFAULT_K_FACTOR: u32 = 32;
+
+const MAX_K_FACTOR: u32 = 100;
+
+// scores, in thousandths
+const WIN: i32 = 1000;
+const DRAW: i32 = 500;
+
+// expected score, in thousandths, of a player rated 0, 25, 50, ... 400 points above their
+// opponent. Gaps over 400 points are counted as 400
+const EXPECTED: [i32; 17] = [
+    500, 536, 571, 606, 640, 673, 703, 733, 760, 785, 808, 830, 849, 867, 882, 896, 909,
+];
+
+fn put_k_factor(e: &Env, k_factor: u32) {
+    let key = DataKey::KFactor;
+    e.storage().set(&key, &k_factor);
+}
+
+fn get_k_factor(e: &Env) -> u32 {
+    let key = DataKey::KFactor;
+    e.storage()
+        .get(&key)
+        .unwrap_or(Ok(DEFAULT_K_FACTOR))
+        .unwrap()
+}
+
+fn put_rating(e: &Env, user: Address, rating: i32) {
+    let key = DataKey::Rating(user);
+    e.storage().set(&key, &rating);
+}
+
+fn get_rating(e: &Env, user: Address) -> i32 {
+    let key = DataKey::Rating(user);
+    e.storage().get(&key).unwrap_or(Ok(INITIAL_RATING)).unwrap()
+}
+
+// expected score, in thousandths, of a player rated `diff` points above their opponent,
+// interpolated between the entries of `EXPECTED`
+fn expected_score(diff: i32) -> i32 {
+    let gap = diff.abs().min(400);
+    let i = (gap / 25) as usize;
+    let mut expected = EXPECTED[i];
+    if i + 1 < EXPECTED.len() {
+        expected += (EXPECTED[i + 1] - EXPECTED[i]) * (gap % 25) / 25;
+    }
+    if diff < 0 {
+        1000 - expected
+    } else {
+        expected
+    }
+}
+
+// moves rating points from `two` to `one` (or back) after `one` scored `score` against them
+fn rate(e: &Env, one: &Address, two: &Address, score: i32) {
+    let one_rating = get_rating(e, one.clone());
+    let two_rating = get_rating(e, two.clone());
+    let delta = get_k_factor(e) as i32 * (score - expected_score(one_rating - two_rating)) / 1000;
+    put_rating(e, one.clone(), one_rating + delta);
+    put_rating(e, two.clone(), two_rating - delta);
+}
+
+// upper bound on the players of a room. Settling a room pays and records every player in the
+// same call, which has to stay within the CPU limit of a single contract call
+const MAX_SEATS: u32 = 6;
+
+// rooms share the id space of games but are stored as a single entry
+fn put_room(e: &Env, room_id: GameId, room: &Room) {
+    let key = DataKey::Room(room_id);
+    e.storage().set(&key, room);
+}
+
+fn get_room(e: &Env, room_id: GameId) -> Result<Room, Error> {
+    let key = DataKey::Room(room_id);
+    match e.storage().get(&key) {
+        Some(room) => Ok(room.unwrap()),
+        None => Err(Error::NoSuchGame),
+    }
+}
+
+// refunds every player of a room their bet minus the house fee, returns what each was sent
+fn refund_room(e: &Env, room_id: GameId, room: &Room) -> i128 {
+    let mut payout = 0;
+    for player in room.players.iter() {
+        let player = player.unwrap();
+        payout = take_fee(e, &room.token, room.bet);
+        send_profit(e, &room.token, player.id.clone(), payout);
+        events::cancelled(e, room_id, player.id, payout);
+    }
+    payout
+}
+
+// splits the pot of a room between the players who revealed the move beating every other move
+// revealed, or between all the revealers when there is no such move. Players who didn't reveal
+// are left out, at least one player has to have revealed
+fn settle_room(e: &Env, room_id: GameId, room: &mut Room, phase: GamePhase) {
+    let mut moves = Vec::new(e);
+    for player in room.players.iter() {
+        let player = player.unwrap();
+        if player.move_pre != Move::Unrevealed {
+            moves.push_back(player.move_pre);
+        }
+    }
+
+    let top = room.ruleset.top_move(&moves);
+    let mut winners = Vec::new(e);
+    for player in room.players.iter() {
+        let player = player.unwrap();
+        if player.move_pre != Move::Unrevealed && (top.is_none() || top == Some(player.move_pre)) {
+            winners.push_back(player.id);
+        }
+    }
+
+    let pot = take_fee(e, &room.token, room.bet * room.players.len() as i128);
+    let share = split_pot(e, &room.token, pot, &winners);
+    for player in room.players.iter() {
+        let player = player.unwrap();
+        if winners.contains(&player.id) {
+            let outcome = if top.is_none() {
+                Outcome::Draw
+            } else {
+                Outcome::Win
+            };
+            record(e, &room.token, &player.id, outcome, room.bet, share);
+        } else if player.move_pre != Move::Unrevealed {
+            record(e, &room.token, &player.id, Outcome::Loss, room.bet, 0);
+        } else {
+            record(e, &room.token, &player.id, Outcome::Forfeit, room.bet, 0);
+        }
+    }
+    room.winners = winners.clone();
+    room.phase = phase;
+    put_room(e, room_id, room);
+    events::room_won(e, room_id, winners, share);
+}
+
+// Perform arithmetic ops on custom types
+trait Arithmetic<Rhs = Self> {
+    type Output;
+
+    fn add(self, rhs: Rhs) -> Self::Output;
+
+    fn sub(self, rhs: Rhs) -> Self::Output;
+}
+
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug)]
+#[contracttype]
+/// Timestamp type to enforce explicitness
+pub struct TimeStamp(pub u64);
+
+impl TimeStamp {
+    fn current(e: &Env) -> Self {
+        Self(e.ledger().timestamp())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[contracttype]
+/// Identifier of a single match hosted by the contract
+pub struct GameId(pub u64);
+
+impl Arithmetic<TimeStamp> for TimeStamp {
+    type Output = TimeStamp;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    GameNotStarted = 1,
+    MaxPlayersHit = 2,
+    InvalidReveal = 3,
+    InvalidOp = 4,
+    NotRevealed = 5,
+    LimitNotReached = 6,
+    InvalidSignature = 7,
+    NoSuchGame = 8,
+    WrongPhase = 9,
+    InvalidFee = 10,
+    Paused = 11,
+    UsedCommit = 12,
+    AlreadyJoined = 13,
+    NotAllowed = 14,
+    InvalidBet = 15,
+    TokenNotAllowed = 16,
+    InvalidRounds = 17,
+    AlreadyCommitted = 18,
+    InvalidMove = 19,
+    InvalidSeats = 20,
+    InvalidPrizes = 21,
+    InvalidKFactor = 22,
+    InvalidSeason = 23,
+    NoSuchSeason = 24,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Player {
+    One,
+    Two,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+/// Lifecycle of a single game
+pub enum GamePhase {
+    /// waiting for the two commitments
+    Open = 0,
+    /// both players committed, nobody revealed yet
+    Committed = 1,
+    /// at least one player revealed
+    Revealing = 2,
+    /// evaluated and paid out
+    Settled = 3,
+    /// closed through `cancel`
+    Cancelled = 4,
+    /// a round that doesn't decide the game was evaluated, waiting for the commitments to the
+    /// next one
+    NextRound = 5,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    Winner(Player),
+    Draw,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum Move {
+    Rock = 0,
+    Paper = 1,
+    Scissors = 2,
+    Unrevealed = 3,
+    Lizard = 4,
+    Spock = 5,
+}
+
+impl Move {
+    pub fn as_bytes(&self, env: &Env) -> Bytes {
+        match self {
+            Move::Rock => bytes!(env, 0x526f636b),
+            Move::Paper => bytes!(env, 0x5061706572),
+            Move::Scissors => bytes!(env, 0x53636973736f7273),
+            Move::Lizard => bytes!(env, 0x4c697a617264),
+            Move::Spock => bytes!(env, 0x53706f636b),
+            _ => panic_with_error!(env, Error::InvalidOp),
+        }
+    }
+
+    pub fn repr(&self) -> u32 {
+        *self as u32
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+/// Set of moves a game is played with, see `Variant::ruleset` for its rules
+pub enum Variant {
+    /// rock, paper, scissors
+    Classic = 0,
+    /// rock, paper, scissors, lizard, Spock: every move beats two others
+    Rpsls = 1,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PlayerObj {
+    pub id: Address,
+    pub user_move: BytesN<32>,
+    pub move_pre: Move,
+}
+
+// upper bound on the rounds of a series, a best of 7 at most
+const MAX_ROUNDS: u32 = 7;
+
+// upper bound on the drawn rounds a game can replay
+const MAX_REPLAYS: u32 = 10;
+
+// upper bound on the games returned by a single `history` call
+const MAX_PAGE: u32 = 20;
+
+// prefixed to every commitment preimage, bump it if the preimage layout ever changes
+const COMMIT_TAG: &[u8] = b"rps-commit-v1";
+
+/// Game id the commitments sent to `join_queue` are bound to, since queued players don't know
+/// which game they'll be paired into. The player's address in the preimage and the registry of
+/// used commitments still keep them from being replayed
+pub const QUEUE_GAME: GameId = GameId(u64::MAX);
+
+/// Builds the commitment a player submits through `make_move`: the sha256 of the version tag,
+/// the contract's address, the game id (`QUEUE_GAME` for `join_queue`), the player's address,
+/// the move and the player's secret.
+/// The move is hashed as `Move::as_bytes`, which is how the built-in variants encode it
+pub fn commitment(
+    e: &Env,
+    contract: &Address,
+    game: GameId,
+    user: &Address,
+    user_move: Move,
+    secret: &Bytes,
+) -> BytesN<32> {
+    hash_commitment(e, contract, game, user, &user_move.as_bytes(e), secret)
+}
+
+// `encoded_move` is the move as encoded by the game's ruleset
+fn hash_commitment(
+    e: &Env,
+    contract: &Address,
+    game: GameId,
+    user: &Address,
+    encoded_move: &Bytes,
+    secret: &Bytes,
+) -> BytesN<32> {
+    let mut preimage = Bytes::from_slice(e, COMMIT_TAG);
+    preimage.append(&contract.clone().serialize(e));
+    preimage.append(&game.serialize(e));
+    preimage.append(&user.clone().serialize(e));
+    preimage.append(encoded_move);
+    preimage.append(secret);
+    e.crypto().sha256(&preimage)
+}
+
+impl PlayerObj {
+    pub fn new(id: Address, user_move: BytesN<32>) -> Self {
+        PlayerObj {
+            id,
+            user_move,
+            move_pre: Move::Unrevealed,
+        }
+    }
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// A player waiting in the matchmaking queue, returned by `get_queue`
+pub struct Ticket {
+    pub user: Address,
+    /// commitment to the move the player will play once paired
+    pub commit: BytesN<32>,
+    /// time the player joined the queue
+    pub since: TimeStamp,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Revealed moves and result of one round of a series
+pub struct RoundResult {
+    pub one: Move,
+    pub two: Move,
+    pub result: GameResult,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Bet bounds of an allowlisted token
+pub struct BetBounds {
+    pub min: i128,
+    pub max: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// Settings chosen upon initialization
+pub struct Config {
+    /// tokens games can be played with, see `get_bounds` for their bet bounds
+    pub tokens: Vec<BytesN<32>>,
+    pub ts_limit: TimeStamp,
+    pub join_limit: TimeStamp,
+    /// receives both bets when neither player reveals. It holds one address at most, and bets are
+    /// refunded if it's empty
+    pub treasury: Vec<Address>,
+    /// house fee in basis points taken from every payout
+    pub fee_bps: u32,
+    pub fee_to: Address,
+    pub admin: Address,
+    /// no new games can be created or joined while paused
+    pub paused: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+/// Results of an address over all its settled games and rooms, returned by `get_stats`
+pub struct Stats {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    /// games lost or refunded because the player didn't commit or reveal in time
+    pub forfeits: u32,
+    /// total of the bets placed
+    pub wagered: i128,
+    /// payouts received minus bets placed, fees included
+    pub net_profit: i128,
+}
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Snapshot of a game returned by `get_game`
+pub struct GameView {
+    pub phase: GamePhase,
+    pub creator: Address,
+    /// set when only an invited opponent can join
+    pub private: bool,
+    /// the invited opponent of a private game, empty otherwise
+    pub opponent: Vec<Address>,
+    pub token: BytesN<32>,
+    pub bet: i128,
+    pub variant: Variant,
+    pub ruleset: Ruleset,
+    /// best of how many rounds, 1 for a single throw
+    pub rounds: u32,
+    /// drawn rounds that can be replayed instead of counting as played
+    pub replays: u32,
+    /// drawn rounds replayed so far
+    pub replayed: u32,
+    /// rounds evaluated so far, replayed draws included
+    pub results: Vec<RoundResult>,
+    /// time of the latest commitment, or of the latest evaluated round between rounds. 0 until
+    /// somebody commits
+    pub bet_start: TimeStamp,
+    /// the players who joined so far, player one first
+    pub players: Vec<PlayerObj>,
+    /// only these addresses may join, anyone may if empty
+    pub allow: Vec<Address>,
+    /// these addresses may not join
+    pub deny: Vec<Address>,
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// A finished game as it stood when it was settled or cancelled, returned by `history`
+pub struct History {
+    pub game: GameId,
+    /// `Settled` or `Cancelled`
+    pub phase: GamePhase,
+    pub token: BytesN<32>,
+    /// staked by each player
+    pub bet: i128,
+    /// the players who joined with their commitments and revealed moves, as of the last round
+    /// played. Player one comes first
+    pub players: Vec<PlayerObj>,
+    /// every round evaluated, replayed draws included
+    pub results: Vec<RoundResult>,
+    /// sent to player one, house fee taken
+    pub paid_one: i128,
+    /// sent to player two, house fee taken
+    pub paid_two: i128,
+    /// time of the last commitment or evaluated round, 0 if nobody committed
+    pub bet_start: TimeStamp,
+    /// time the game was settled or cancelled
+    pub ended: TimeStamp,
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// A free-for-all game between three or more players, returned by `get_room`
+pub struct Room {
+    pub creator: Address,
+    pub token: BytesN<32>,
+    /// staked by every player
+    pub bet: i128,
+    pub variant: Variant,
+    pub ruleset: Ruleset,
+    /// players the room is played with, it starts once they all joined
+    pub seats: u32,
+    pub phase: GamePhase,
+    /// time of the latest commitment, or of the room's creation if nobody joined yet
+    pub bet_start: TimeStamp,
+    /// in the order they joined, a player's index in here is their seat
+    pub players: Vec<PlayerObj>,
+    /// players who split the pot, set once the room is settled
+    pub winners: Vec<Address>,
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// Contract data keys
+pub enum DataKey {
+    TsLimit,
+    JoinLimit,
+    Treasury,
+    FeeBps,
+    FeeTo,
+    Fees(BytesN<32>),
+    Tokens,
+    Bounds(BytesN<32>),
+    GameToken(GameId),
+    Admin,
+    Paused,
+    GameBet(GameId),
+    Rounds(GameId),
+    Results(GameId),
+    Replays(GameId),
+    Replayed(GameId),
+    Variant(GameId),
+    Ruleset(GameId),
+    Room(GameId),
+    Tourn(GameId),
+    Match(GameId),
+    Winner(GameId),
+    Stats(Address),
+    KFactor,
+    Rating(Address),
+    Seasons,
+    Season(u32),
+    SeasonNet(u32, Address),
+    History(GameId),
+    HistLen(Address),
+    HistGame(Address, u32),
+    Queue(BytesN<32>, i128),
+    Queued(GameId),
+    Next(GameId, Player),
+    Commit(Address, BytesN<32>),
+    Creator(GameId),
+    Opponent(GameId),
+    Allow(GameId),
+    Deny(GameId),
+    Started,
+    GameCount,
+    Phase(GameId),
+    BetStart(GameId),
+    Player(GameId, Player),
+}
+
+/// Contract trait
+pub trait RockPaperScissorsTrait {
+    // leaving this one for possible updates in the future that need a contract initialization
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        e: Env,
+        token: BytesN<32>,
+        min_bet: i128,
+        max_bet: i128,
+        ts_diff: TimeStamp,
+        join_diff: TimeStamp,
+        treasury: Option<Address>,
+        fee_bps: u32,
+        fee_to: Address,
+        k_factor: u32,
+        admin: Address,
+    ) -> Result<(), Error>;
+
+    // contract function names can't be longer than 10 characters, hence `new_game` rather than `create_game`
+    // with an `opponent` the game is private: only the creator can take the first slot and
+    // only the opponent the second one. The opponent can't be the creator
+    // `rounds` makes the game a best of `rounds` series, it has to be odd and 7 at most
+    // up to `replays` drawn rounds are replayed (sudden death) instead of counting as played,
+    // a single throw refunds both players on a draw without them
+    #[allow(clippy::too_many_arguments)]
+    fn new_game(
+        e: Env,
+        creator: Address,
+        opponent: Option<Address>,
+        token: BytesN<32>,
+        bet: i128,
+        variant: Variant,
+        rounds: u32,
+        replays: u32,
+    ) -> Result<GameId, Error>;
+
+    // `bet` has to match the game's bet so that players explicitly accept the stake
+    fn make_move(
+        e: Env,
+        game: GameId,
+        user: Address,
+        user_move: BytesN<32>,
+        bet: i128,
+    ) -> Result<(), Error>;
+
+    // commits a player to their move for the next round of a series or for a replayed draw,
+    // no bet is placed
+    fn next_move(e: Env, game: GameId, user: Address, user_move: BytesN<32>) -> Result<(), Error>;
+
+    fn reveal(
+        e: Env,
+        game: GameId,
+        player: Player,
+        user_move: Move,
+        secret: Bytes,
+    ) -> Result<Move, Error>;
+
+    // evaluates the current round and returns its result, the stake is only paid out once
+    // the series is decided
+    fn evaluate(e: Env, game: GameId) -> Result<GameResult, Error>;
+
+    fn cancel(e: Env, game: GameId) -> Result<(), Error>;
+
+    // creator only, while the game is open. An empty allow list lets anyone join
+    fn set_lists(
+        e: Env,
+        game: GameId,
+        allow: Vec<Address>,
+        deny: Vec<Address>,
+    ) -> Result<(), Error>;
+
+    fn get_game(e: Env, game: GameId) -> Result<GameView, Error>;
+
+    // finished games `user` played in, oldest first, starting from the `cursor`th one. At most
+    // `limit` of them are returned, and no more than 20: pass `cursor` plus the number of games
+    // returned to get the next page. Named `history` rather than `get_history` to fit in 10
+    // characters
+    fn history(e: Env, user: Address, cursor: u32, limit: u32) -> Vec<History>;
+
+    fn get_player(e: Env, game: GameId, player: Player) -> Result<PlayerObj, Error>;
+
+    fn get_config(e: Env) -> Result<Config, Error>;
+
+    // free-for-all rooms of 3 to 6 players, they share ids with games. Rooms start as soon as
+    // all `seats` are taken
+    fn new_room(
+        e: Env,
+        creator: Address,
+        token: BytesN<32>,
+        bet: i128,
+        variant: Variant,
+        seats: u32,
+    ) -> Result<GameId, Error>;
+
+    fn join_room(
+        e: Env,
+        room_id: GameId,
+        user: Address,
+        user_move: BytesN<32>,
+        bet: i128,
+    ) -> Result<(), Error>;
+
+    // the `reveal` of rooms, `seat` is the player's index in `Room::players`
+    fn reveal_at(
+        e: Env,
+        room_id: GameId,
+        seat: u32,
+        user_move: Move,
+        secret: Bytes,
+    ) -> Result<Move, Error>;
+
+    // once everybody revealed, splits the pot between the players whose move beats all the other
+    // moves revealed, or between everybody if there is no such move. Returns the winners
+    fn eval_room(e: Env, room_id: GameId) -> Result<Vec<Address>, Error>;
+
+    // refunds the players of a room that didn't fill up within the join limit. After the reveal
+    // limit, the room is settled between the players who revealed, or refunded (forfeited to the
+    // treasury if there is one) if nobody did
+    fn drop_room(e: Env, room_id: GameId) -> Result<(), Error>;
+
+    fn get_room(e: Env, room_id: GameId) -> Result<Room, Error>;
+
+    // single-elimination tournaments of 4 to 8 players (a power of two), they share ids with
+    // games. `prizes` are the shares of the pot in basis points for the champion, the runner-up
+    // and the semifinal losers, they have to add up to 10000
+    fn new_tourn(
+        e: Env,
+        creator: Address,
+        token: BytesN<32>,
+        fee: i128,
+        variant: Variant,
+        size: u32,
+        prizes: Vec<u32>,
+    ) -> Result<GameId, Error>;
+
+    // registers `user` and takes their entry fee, players are seeded in the order they register.
+    // The first round is paired once registration is full, the games of the bracket are listed in
+    // `Tournament::matches` and played like any other game, with a bet of 0
+    fn join_tourn(e: Env, tourn_id: GameId, user: Address) -> Result<(), Error>;
+
+    // refunds the players of a tournament that didn't fill up within the join limit
+    fn drop_tourn(e: Env, tourn_id: GameId) -> Result<(), Error>;
+
+    fn get_tourn(e: Env, tourn_id: GameId) -> Result<Tournament, Error>;
+
+    // matchmaking: pairs `user` with the player waiting for an opponent at `stake` in `token`
+    // and returns the game, which starts in the `Committed` phase with that
+    // player as player one. `user` is queued and None is returned when nobody is waiting. The
+    // stake is escrowed either way, and `user_move` has to be bound to `QUEUE_GAME`. Queued games
+    // are single throws of the classic variant
+    fn join_queue(
+        e: Env,
+        user: Address,
+        token: BytesN<32>,
+        stake: i128,
+        user_move: BytesN<32>,
+    ) -> Result<Option<GameId>, Error>;
+
+    // takes `user` out of the queue and refunds their stake minus the house fee
+    fn drop_queue(e: Env, user: Address, token: BytesN<32>, stake: i128) -> Result<(), Error>;
+
+    // the player waiting for an opponent at `stake` in `token`, if any
+    fn get_queue(e: Env, token: BytesN<32>, stake: i128) -> Option<Ticket>;
+
+    // wins, losses, draws and forfeits of `user` over settled games and rooms, with the amounts
+    // wagered and won. Tournament fees and prizes aren't counted
+    fn get_stats(e: Env, user: Address) -> Stats;
+
+    // Elo rating of `user`, starting at 1500. It moves when one of their games is decided in
+    // `evaluate` or won by their opponent in `cancel` because they didn't commit or reveal in
+    // time, by at most the K-factor set at initialization
+    fn get_rating(e: Env, user: Address) -> i32;
+
+    // admin only, schedules a season of play in `token` from `start` to `end`. Seasons can't
+    // overlap and are numbered from 0. `prizes` are the shares of the prize pool in basis points
+    // for the first, second, ... players of the leaderboard, they have to add up to 10000
+    fn new_season(
+        e: Env,
+        token: BytesN<32>,
+        start: TimeStamp,
+        end: TimeStamp,
+        prizes: Vec<u32>,
+    ) -> Result<u32, Error>;
+
+    // admin only, adds `amount` of the season's token to its prize pool
+    fn fund_pool(e: Env, season: u32, amount: i128) -> Result<(), Error>;
+
+    // pays out the prize pool of a season that's over to the top of its leaderboard
+    fn end_season(e: Env, season: u32) -> Result<(), Error>;
+
+    fn get_season(e: Env, season: u32) -> Result<Season, Error>;
+
+    // seconds left before `cancel` can be called on the game, 0 if it already can
+    fn cancel_in(e: Env, game: GameId) -> Result<u64, Error>;
+
+    fn get_bounds(e: Env, token: BytesN<32>) -> Result<BetBounds, Error>;
+
+    // fees collected in `token` and not claimed yet
+    fn get_fees(e: Env, token: BytesN<32>) -> i128;
+
+    // sends the fees collected in `token` to the fee recipient
+    fn claim_fees(e: Env, token: BytesN<32>) -> Result<i128, Error>;
+
+    // admin only, allowlists `token` or updates its bounds. Running games aren't affected
+    fn add_token(e: Env, token: BytesN<32>, min_bet: i128, max_bet: i128) -> Result<(), Error>;
+
+    // admin only, games already using `token` can still be played and settled
+    fn rm_token(e: Env, token: BytesN<32>) -> Result<(), Error>;
+
+    // admin only
+    fn set_limit(e: Env, ts_diff: TimeStamp) -> Result<(), Error>;
+
+    // admin only, stops new games from being created or joined
+    fn pause(e: Env) -> Result<(), Error>;
+
+    // admin only
+    fn unpause(e: Env) -> Result<(), Error>;
+
+    // admin only, the new admin has to authorize too
+    fn set_admin(e: Env, new_admin: Address) -> Result<(), Error>;
+}
+
+pub struct RockPaperScissorsContract;
+
+#[contractimpl]
+impl RockPaperScissorsTrait for RockPaperScissorsContract {
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        e: Env,
+        token: BytesN<32>,
+        min_bet: i128,
+        max_bet: i128,
+        ts_diff: TimeStamp,
+        join_diff: TimeStamp,
+        treasury: Option<Address>,
+        fee_bps: u32,
+        fee_to: Address,
+        k_factor: u32,
+        admin: Address,
+    ) -> Result<(), Error> {
+        if fee_bps > 10_000 {
+            return Err(Error::InvalidFee);
+        }
+
+        if !valid_bounds(min_bet, max_bet) {
+            return Err(Error::InvalidBet);
+        }
+
+        if k_factor == 0 || k_factor > MAX_K_FACTOR {
+            return Err(Error::InvalidKFactor);
+        }
+
+        if !game_started(&e) {
+            admin.require_auth();
+            put_started(&e, true);
+            put_token(
+                &e,
+                token.clone(),
+                BetBounds {
+                    min: min_bet,
+                    max: max_bet,
+                },
+            );
+            put_ts_limit(&e, ts_diff.clone());
+            put_join_limit(&e, join_diff.clone());
+            if let Some(treasury) = treasury {
+                put_treasury(&e, treasury);
+            }
+            put_fee_bps(&e, fee_bps);
+            put_fee_to(&e, fee_to);
+            put_k_factor(&e, k_factor);
+            put_admin(&e, admin);
+            events::initialized(&e, token, min_bet, max_bet, ts_diff, join_diff);
+            Ok(())
+        } else {
+            Err(Error::GameNotStarted)
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_game(
+        e: Env,
+        creator: Address,
+        opponent: Option<Address>,
+        token: BytesN<32>,
+        bet: i128,
+        variant: Variant,
+        rounds: u32,
+        replays: u32,
+    ) -> Result<GameId, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        creator.require_auth();
+
+        // the creator takes the first slot, inviting themselves would leave nobody for the second
+        if opponent.as_ref() == Some(&creator) {
+            return Err(Error::NotAllowed);
+        }
+
+        let bounds = get_bounds(&e, token.clone()).ok_or(Error::TokenNotAllowed)?;
+        if bet < bounds.min || bet > bounds.max {
+            return Err(Error::InvalidBet);
+        }
+
+        if rounds % 2 == 0 || rounds > MAX_ROUNDS || replays > MAX_REPLAYS {
+            return Err(Error::InvalidRounds);
+        }
+
+        let game = next_game_id(&e);
+        put_phase(&e, game, GamePhase::Open);
+        put_creator(&e, game, creator.clone());
+        if let Some(opponent) = opponent {
+            put_opponent(&e, game, opponent);
+        }
+        put_game_token(&e, game, token.clone());
+        put_game_bet(&e, game, bet);
+        put_variant(&e, game, variant);
+        put_ruleset(&e, game, variant.ruleset(&e));
+        put_rounds(&e, game, rounds);
+        put_replays(&e, game, replays);
+        events::created(&e, game, creator, token, bet, variant, rounds);
+        Ok(game)
+    }
+
+    fn make_move(
+        e: Env,
+        game: GameId,
+        user: Address,
+        user_move: BytesN<32>,
+        bet: i128,
+    ) -> Result<(), Error> {
+        require_phase(&e, game, &[GamePhase::Open])?;
+
+        if bet != get_game_bet(&e, game) {
+            return Err(Error::InvalidBet);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        user.require_auth();
+
+        if !can_join(&e, game, &user) {
+            return Err(Error::NotAllowed);
+        }
+
+        if let Some(p1_obj) = get_player_opt(&e, game, Player::One) {
+            if p1_obj.id == user {
+                return Err(Error::AlreadyJoined);
+            }
+        }
+
+        if commitment_used(&e, user.clone(), user_move.clone()) {
+            return Err(Error::UsedCommit);
+        }
+        put_commitment_used(&e, user.clone(), user_move.clone());
+
+        let player_obj = PlayerObj::new(user.clone(), user_move);
+
+        if !check_player(&e, game, Player::One) {
+            store_move(&e, game, Player::One, player_obj);
+            place_bet(&e, &get_game_token(&e, game), user.clone(), bet);
+            put_bet_start(&e, game, TimeStamp::current(&e));
+            events::joined(&e, game, user, Player::One, get_game_bet(&e, game));
+            Ok(())
+        } else if !check_player(&e, game, Player::Two) {
+            store_move(&e, game, Player::Two, player_obj);
+            place_bet(&e, &get_game_token(&e, game), user.clone(), bet);
+            put_bet_start(&e, game, TimeStamp::current(&e));
+            put_phase(&e, game, GamePhase::Committed);
+            events::joined(&e, game, user, Player::Two, get_game_bet(&e, game));
+            Ok(())
+        } else {
+            Err(Error::MaxPlayersHit)
+        }
+    }
+
+    fn next_move(e: Env, game: GameId, user: Address, user_move: BytesN<32>) -> Result<(), Error> {
+        require_phase(&e, game, &[GamePhase::NextRound])?;
+
+        user.require_auth();
+
+        let (player, other) = if get_move(&e, game, Player::One).id == user {
+            (Player::One, Player::Two)
+        } else if get_move(&e, game, Player::Two).id == user {
+            (Player::Two, Player::One)
+        } else {
+            return Err(Error::NotAllowed);
+        };
+
+        if get_next(&e, game, player.clone()).is_some() {
+            return Err(Error::AlreadyCommitted);
+        }
+
+        if commitment_used(&e, user.clone(), user_move.clone()) {
+            return Err(Error::UsedCommit);
+        }
+        put_commitment_used(&e, user.clone(), user_move.clone());
+
+        put_next(&e, game, player.clone(), user_move);
+        events::committed(&e, game, user, player, get_results(&e, game).len() + 1);
+
+        if get_next(&e, game, other).is_some() {
+            start_round(&e, game);
+        }
+        Ok(())
+    }
+
+    // doesn't need authenticating since the revealer needs to know the secret
+    // the account id for the hash is only needed so that the hash image doesn't coincide if the same moves are hashed with the same secrets by two different users
+    // the contract address and game id bind the commitment to this game so that it can't be replayed elsewhere
+    fn reveal(
+        e: Env,
+        game: GameId,
+        player: Player,
+        user_move: Move,
+        secret: Bytes,
+    ) -> Result<Move, Error> {
+        require_phase(&e, game, &[GamePhase::Committed, GamePhase::Revealing])?;
+
+        let encoded_move = get_ruleset(&e, game)
+            .encode(user_move)
+            .ok_or(Error::InvalidMove)?;
+
+        let mut player_obj = get_move(&e, game, player.clone());
+
+        let rhs_hash = hash_commitment(
+            &e,
+            &e.current_contract_address(),
+            commit_game(&e, game),
+            &player_obj.id,
+            &encoded_move,
+            &secret,
+        );
+
+        if player_obj.user_move != rhs_hash {
+            return Err(Error::InvalidReveal);
+        }
+
+        player_obj.move_pre = user_move;
+        store_move(&e, game, player.clone(), player_obj.clone());
+        put_phase(&e, game, GamePhase::Revealing);
+        events::revealed(&e, game, player_obj.id, player, user_move);
+        Ok(user_move)
+    }
+
+    fn evaluate(e: Env, game: GameId) -> Result<GameResult, Error> {
+        require_phase(&e, game, &[GamePhase::Revealing])?;
+
+        // check that both players have revealed
+        if !check_revealed(&e, game, Player::One) || !check_revealed(&e, game, Player::Two) {
+            return Err(Error::NotRevealed);
+        }
+
+        let p1_obj = get_move(&e, game, Player::One);
+        let p2_obj = get_move(&e, game, Player::Two);
+        let bet = get_game_bet(&e, game);
+        let token = get_game_token(&e, game);
+
+        let result = get_ruleset(&e, game).result(p1_obj.move_pre, p2_obj.move_pre);
+        let mut results = get_results(&e, game);
+        results.push_back(RoundResult {
+            one: p1_obj.move_pre,
+            two: p2_obj.move_pre,
+            result: result.clone(),
+        });
+        put_results(&e, game, results.clone());
+        events::round(&e, game, results.len(), result.clone());
+
+        let mut replayed = get_replayed(&e, game);
+        if result == GameResult::Draw && replayed < get_replays(&e, game) {
+            replayed += 1;
+            put_replayed(&e, game, replayed);
+        }
+
+        match series_result(&results, get_rounds(&e, game), replayed) {
+            Some(GameResult::Winner(winner)) => {
+                let (to, loser) = if winner == Player::One {
+                    (p1_obj.id, p2_obj.id)
+                } else {
+                    (p2_obj.id, p1_obj.id)
+                };
+                let payout = pay_out(&e, game, to.clone(), bet * 2);
+                if winner == Player::One {
+                    close_game(&e, game, GamePhase::Settled, payout, 0);
+                } else {
+                    close_game(&e, game, GamePhase::Settled, 0, payout);
+                }
+                record(&e, &token, &to, Outcome::Win, bet, payout);
+                record(&e, &token, &loser, Outcome::Loss, bet, 0);
+                rate(&e, &to, &loser, WIN);
+                events::settled(&e, game, GameResult::Winner(winner), payout);
+                tournament::match_over(&e, game, Some(to));
+            }
+            Some(GameResult::Draw) => {
+                // give back the betted money to both players
+                pay_out(&e, game, p1_obj.id.clone(), bet);
+                let payout = pay_out(&e, game, p2_obj.id.clone(), bet);
+                close_game(&e, game, GamePhase::Settled, payout, payout);
+                record(&e, &token, &p1_obj.id, Outcome::Draw, bet, payout);
+                record(&e, &token, &p2_obj.id, Outcome::Draw, bet, payout);
+                rate(&e, &p1_obj.id, &p2_obj.id, DRAW);
+                events::settled(&e, game, GameResult::Draw, payout);
+                tournament::match_over(&e, game, None);
+            }
+            None => {
+                // on to the next round, or to the replay of the draw. Its reveal limit runs from
+                // its commitments, this one bounds how long the players have to send them
+                put_bet_start(&e, game, TimeStamp::current(&e));
+                put_phase(&e, game, GamePhase::NextRound);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn cancel(e: Env, game: GameId) -> Result<(), Error> {
+        let phase = require_phase(
+            &e,
+            game,
+            &[
+                GamePhase::Open,
+                GamePhase::Committed,
+                GamePhase::Revealing,
+                GamePhase::NextRound,
+            ],
+        )?;
+        let bet = get_game_bet(&e, game);
+        let token = get_game_token(&e, game);
+
+        if phase == GamePhase::Open {
+            // nobody joined player one before the join limit, refund their bet
+            let p_obj = get_player_opt(&e, game, Player::One);
+            if p_obj.is_none() && !tournament::is_match(&e, game) {
+                return Err(Error::InvalidOp);
+            }
+
+            // tournament matches start their join limit when they're paired
+            if !limit_reached(&e, get_bet_start(&e, game), get_join_limit(&e)) {
+                return Err(Error::LimitNotReached);
+            }
+
+            let p_obj = match p_obj {
+                Some(p_obj) => p_obj,
+                None => {
+                    // nobody showed up to the tournament match
+                    close_game(&e, game, GamePhase::Cancelled, 0, 0);
+                    tournament::match_over(&e, game, None);
+                    return Ok(());
+                }
+            };
+
+            let payout = pay_out(&e, game, p_obj.id.clone(), bet);
+            close_game(&e, game, GamePhase::Cancelled, payout, 0);
+            events::cancelled(&e, game, p_obj.id.clone(), payout);
+            tournament::match_over(&e, game, Some(p_obj.id));
+            return Ok(());
+        }
+
+        if !limit_reached(&e, get_bet_start(&e, game), get_ts_limit(&e)) {
+            return Err(Error::LimitNotReached);
+        }
+
+        // whether each player did their part of the current step: committing to the next round
+        // between the rounds of a series, revealing otherwise
+        let (p1_done, p2_done) = if phase == GamePhase::NextRound {
+            (
+                get_next(&e, game, Player::One).is_some(),
+                get_next(&e, game, Player::Two).is_some(),
+            )
+        } else {
+            (
+                check_revealed(&e, game, Player::One),
+                check_revealed(&e, game, Player::Two),
+            )
+        };
+
+        if !p1_done && !p2_done {
+            // neither player went on: forfeit both bets to the treasury if there is one,
+            // refund them otherwise
+            let p1_obj = get_move(&e, game, Player::One);
+            let p2_obj = get_move(&e, game, Player::Two);
+
+            let refund = if let Some(treasury) = get_treasury(&e) {
+                let payout = pay_out(&e, game, treasury.clone(), bet * 2);
+                events::cancelled(&e, game, treasury, payout);
+                0
+            } else {
+                let payout = pay_out(&e, game, p1_obj.id.clone(), bet);
+                pay_out(&e, game, p2_obj.id.clone(), bet);
+                events::cancelled(&e, game, p1_obj.id.clone(), payout);
+                events::cancelled(&e, game, p2_obj.id.clone(), payout);
+                payout
+            };
+            close_game(&e, game, GamePhase::Cancelled, refund, refund);
+            record(&e, &token, &p1_obj.id, Outcome::Forfeit, bet, refund);
+            record(&e, &token, &p2_obj.id, Outcome::Forfeit, bet, refund);
+            tournament::match_over(&e, game, None);
+            return Ok(());
+        }
+
+        let (p_obj, other_obj) = if !p1_done && p2_done {
+            (
+                get_move(&e, game, Player::Two),
+                get_move(&e, game, Player::One),
+            )
+        } else if p1_done && !p2_done {
+            (
+                get_move(&e, game, Player::One),
+                get_move(&e, game, Player::Two),
+            )
+        } else {
+            return Err(Error::LimitNotReached);
+        };
+
+        let payout = pay_out(&e, game, p_obj.id.clone(), bet * 2);
+        if p1_done {
+            close_game(&e, game, GamePhase::Cancelled, payout, 0);
+        } else {
+            close_game(&e, game, GamePhase::Cancelled, 0, payout);
+        }
+        record(&e, &token, &p_obj.id, Outcome::Win, bet, payout);
+        record(&e, &token, &other_obj.id, Outcome::Forfeit, bet, 0);
+        rate(&e, &p_obj.id, &other_obj.id, WIN);
+        events::cancelled(&e, game, p_obj.id.clone(), payout);
+        tournament::match_over(&e, game, Some(p_obj.id));
+        Ok(())
+    }
+
+    fn set_lists(
+        e: Env,
+        game: GameId,
+        allow: Vec<Address>,
+        deny: Vec<Address>,
+    ) -> Result<(), Error> {
+        require_phase(&e, game, &[GamePhase::Open])?;
+
+        // the allow list of a tournament match holds its two players
+        if tournament::is_match(&e, game) {
+            return Err(Error::NotAllowed);
+        }
+
+        get_creator(&e, game).require_auth();
+        put_allow(&e, game, allow);
+        put_deny(&e, game, deny);
+        Ok(())
+    }
+
+    fn history(e: Env, user: Address, cursor: u32, limit: u32) -> Vec<History> {
+        let end = get_history_len(&e, user.clone()).min(cursor.saturating_add(limit.min(MAX_PAGE)));
+        let mut page = Vec::new(&e);
+        for i in cursor..end {
+            let game: GameId = e
+                .storage()
+                .get(&DataKey::HistGame(user.clone(), i))
+                .unwrap()
+                .unwrap();
+            page.push_back(get_history(&e, game));
+        }
+        page
+    }
+
+    fn get_game(e: Env, game: GameId) -> Result<GameView, Error> {
+        let phase = get_phase(&e, game)?;
+        let mut opponent = Vec::new(&e);
+        if let Some(address) = get_opponent(&e, game) {
+            opponent.push_back(address);
+        }
+
+        Ok(GameView {
+            phase,
+            creator: get_creator(&e, game),
+            private: !opponent.is_empty(),
+            opponent,
+            token: get_game_token(&e, game),
+            bet: get_game_bet(&e, game),
+            variant: get_variant(&e, game),
+            ruleset: get_ruleset(&e, game),
+            rounds: get_rounds(&e, game),
+            replays: get_replays(&e, game),
+            replayed: get_replayed(&e, game),
+            results: get_results(&e, game),
+            bet_start: get_bet_start_opt(&e, game).unwrap_or(TimeStamp(0)),
+            players: get_players(&e, game),
+            allow: get_allow(&e, game),
+            deny: get_deny(&e, game),
+        })
+    }
+
+    fn get_player(e: Env, game: GameId, player: Player) -> Result<PlayerObj, Error> {
+        get_phase(&e, game)?;
+        get_player_opt(&e, game, player).ok_or(Error::InvalidOp)
+    }
+
+    fn get_config(e: Env) -> Result<Config, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        let mut treasury = Vec::new(&e);
+        if let Some(address) = get_treasury(&e) {
+            treasury.push_back(address);
+        }
+
+        Ok(Config {
+            tokens: get_tokens(&e),
+            ts_limit: get_ts_limit(&e),
+            join_limit: get_join_limit(&e),
+            treasury,
+            fee_bps: get_fee_bps(&e),
+            fee_to: get_fee_to(&e),
+            admin: get_admin(&e),
+            paused: is_paused(&e),
+        })
+    }
+
+    fn new_room(
+        e: Env,
+        creator: Address,
+        token: BytesN<32>,
+        bet: i128,
+        variant: Variant,
+        seats: u32,
+    ) -> Result<GameId, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        creator.require_auth();
+
+        let bounds = get_bounds(&e, token.clone()).ok_or(Error::TokenNotAllowed)?;
+        if bet < bounds.min || bet > bounds.max {
+            return Err(Error::InvalidBet);
+        }
+
+        if !(3..=MAX_SEATS).contains(&seats) {
+            return Err(Error::InvalidSeats);
+        }
+
+        let room_id = next_game_id(&e);
+        put_room(
+            &e,
+            room_id,
+            &Room {
+                creator: creator.clone(),
+                token: token.clone(),
+                bet,
+                variant,
+                ruleset: variant.ruleset(&e),
+                seats,
+                phase: GamePhase::Open,
+                bet_start: TimeStamp::current(&e),
+                players: Vec::new(&e),
+                winners: Vec::new(&e),
+            },
+        );
+        events::room_created(&e, room_id, creator, token, bet, seats);
+        Ok(room_id)
+    }
+
+    fn join_room(
+        e: Env,
+        room_id: GameId,
+        user: Address,
+        user_move: BytesN<32>,
+        bet: i128,
+    ) -> Result<(), Error> {
+        let mut room = get_room(&e, room_id)?;
+
+        if room.phase != GamePhase::Open {
+            return Err(Error::WrongPhase);
+        }
+
+        if bet != room.bet {
+            return Err(Error::InvalidBet);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        user.require_auth();
+
+        if room.players.iter().any(|player| player.unwrap().id == user) {
+            return Err(Error::AlreadyJoined);
+        }
+
+        if commitment_used(&e, user.clone(), user_move.clone()) {
+            return Err(Error::UsedCommit);
+        }
+        put_commitment_used(&e, user.clone(), user_move.clone());
+
+        place_bet(&e, &room.token, user.clone(), room.bet);
+        room.players
+            .push_back(PlayerObj::new(user.clone(), user_move));
+        room.bet_start = TimeStamp::current(&e);
+        if room.players.len() == room.seats {
+            room.phase = GamePhase::Committed;
+        }
+        put_room(&e, room_id, &room);
+        events::room_joined(&e, room_id, user, room.players.len() - 1, room.bet);
+        Ok(())
+    }
+
+    fn reveal_at(
+        e: Env,
+        room_id: GameId,
+        seat: u32,
+        user_move: Move,
+        secret: Bytes,
+    ) -> Result<Move, Error> {
+        let mut room = get_room(&e, room_id)?;
+
+        if !matches!(room.phase, GamePhase::Committed | GamePhase::Revealing) {
+            return Err(Error::WrongPhase);
+        }
+
+        let encoded_move = room.ruleset.encode(user_move).ok_or(Error::InvalidMove)?;
+        let mut player_obj = room.players.get(seat).ok_or(Error::InvalidOp)?.unwrap();
+
+        let rhs_hash = hash_commitment(
+            &e,
+            &e.current_contract_address(),
+            room_id,
+            &player_obj.id,
+            &encoded_move,
+            &secret,
+        );
+
+        if player_obj.user_move != rhs_hash {
+            return Err(Error::InvalidReveal);
+        }
+
+        player_obj.move_pre = user_move;
+        room.players.set(seat, player_obj.clone());
+        room.phase = GamePhase::Revealing;
+        put_room(&e, room_id, &room);
+        events::room_revealed(&e, room_id, player_obj.id, seat, user_move);
+        Ok(user_move)
+    }
+
+    fn eval_room(e: Env, room_id: GameId) -> Result<Vec<Address>, Error> {
+        let mut room = get_room(&e, room_id)?;
+
+        if room.phase != GamePhase::Revealing {
+            return Err(Error::WrongPhase);
+        }
+
+        if room
+            .players
+            .iter()
+            .any(|player| player.unwrap().move_pre == Move::Unrevealed)
+        {
+            return Err(Error::NotRevealed);
+        }
+
+        settle_room(&e, room_id, &mut room, GamePhase::Settled);
+        Ok(room.winners)
+    }
+
+    fn drop_room(e: Env, room_id: GameId) -> Result<(), Error> {
+        let mut room = get_room(&e, room_id)?;
+
+        match room.phase {
+            GamePhase::Open => {
+                if !limit_reached(&e, room.bet_start.clone(), get_join_limit(&e)) {
+                    return Err(Error::LimitNotReached);
+                }
+
+                refund_room(&e, room_id, &room);
+            }
+            GamePhase::Revealing => {
+                if !limit_reached(&e, room.bet_start.clone(), get_ts_limit(&e)) {
+                    return Err(Error::LimitNotReached);
+                }
+
+                settle_room(&e, room_id, &mut room, GamePhase::Cancelled);
+                return Ok(());
+            }
+            GamePhase::Committed => {
+                // nobody revealed
+                if !limit_reached(&e, room.bet_start.clone(), get_ts_limit(&e)) {
+                    return Err(Error::LimitNotReached);
+                }
+
+                let refund = if let Some(treasury) = get_treasury(&e) {
+                    let payout = take_fee(&e, &room.token, room.bet * room.players.len() as i128);
+                    send_profit(&e, &room.token, treasury.clone(), payout);
+                    events::cancelled(&e, room_id, treasury, payout);
+                    0
+                } else {
+                    refund_room(&e, room_id, &room)
+                };
+                for player in room.players.iter() {
+                    record(
+                        &e,
+                        &room.token,
+                        &player.unwrap().id,
+                        Outcome::Forfeit,
+                        room.bet,
+                        refund,
+                    );
+                }
+            }
+            _ => return Err(Error::WrongPhase),
+        }
+
+        room.phase = GamePhase::Cancelled;
+        put_room(&e, room_id, &room);
+        Ok(())
+    }
+
+    fn get_room(e: Env, room_id: GameId) -> Result<Room, Error> {
+        get_room(&e, room_id)
+    }
+
+    fn new_tourn(
+        e: Env,
+        creator: Address,
+        token: BytesN<32>,
+        fee: i128,
+        variant: Variant,
+        size: u32,
+        prizes: Vec<u32>,
+    ) -> Result<GameId, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        creator.require_auth();
+
+        let bounds = get_bounds(&e, token.clone()).ok_or(Error::TokenNotAllowed)?;
+        if fee < bounds.min || fee > bounds.max {
+            return Err(Error::InvalidBet);
+        }
+
+        if !(4..=tournament::MAX_PLAYERS).contains(&size) || !size.is_power_of_two() {
+            return Err(Error::InvalidSeats);
+        }
+
+        if !tournament::valid_prizes(&prizes) {
+            return Err(Error::InvalidPrizes);
+        }
+
+        let tourn_id = next_game_id(&e);
+        tournament::put_tournament(
+            &e,
+            tourn_id,
+            &Tournament {
+                creator: creator.clone(),
+                token: token.clone(),
+                fee,
+                variant,
+                size,
+                prizes,
+                phase: GamePhase::Open,
+                bet_start: TimeStamp::current(&e),
+                players: Vec::new(&e),
+                matches: Vec::new(&e),
+                ranking: Vec::new(&e),
+            },
+        );
+        events::tourn_created(&e, tourn_id, creator, token, fee, size);
+        Ok(tourn_id)
+    }
+
+    fn join_tourn(e: Env, tourn_id: GameId, user: Address) -> Result<(), Error> {
+        let mut tourn = tournament::get_tournament(&e, tourn_id)?;
+
+        if tourn.phase != GamePhase::Open {
+            return Err(Error::WrongPhase);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        user.require_auth();
+
+        if tourn.players.contains(&user) {
+            return Err(Error::AlreadyJoined);
+        }
+
+        place_bet(&e, &tourn.token, user.clone(), tourn.fee);
+        tourn.players.push_back(user.clone());
+        tourn.bet_start = TimeStamp::current(&e);
+        events::tourn_joined(&e, tourn_id, user, tourn.players.len() - 1);
+
+        if tourn.players.len() == tourn.size {
+            tournament::start(&e, tourn_id, &mut tourn);
+        }
+        tournament::put_tournament(&e, tourn_id, &tourn);
+        Ok(())
+    }
+
+    fn drop_tourn(e: Env, tourn_id: GameId) -> Result<(), Error> {
+        let mut tourn = tournament::get_tournament(&e, tourn_id)?;
+
+        if tourn.phase != GamePhase::Open {
+            return Err(Error::WrongPhase);
+        }
+
+        if !limit_reached(&e, tourn.bet_start.clone(), get_join_limit(&e)) {
+            return Err(Error::LimitNotReached);
+        }
+
+        tournament::refund(&e, tourn_id, &tourn);
+        tourn.phase = GamePhase::Cancelled;
+        tournament::put_tournament(&e, tourn_id, &tourn);
+        Ok(())
+    }
+
+    fn get_tourn(e: Env, tourn_id: GameId) -> Result<Tournament, Error> {
+        tournament::get_tournament(&e, tourn_id)
+    }
+
+    fn join_queue(
+        e: Env,
+        user: Address,
+        token: BytesN<32>,
+        stake: i128,
+        user_move: BytesN<32>,
+    ) -> Result<Option<GameId>, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        if is_paused(&e) {
+            return Err(Error::Paused);
+        }
+
+        user.require_auth();
+
+        let bounds = get_bounds(&e, token.clone()).ok_or(Error::TokenNotAllowed)?;
+        if stake < bounds.min || stake > bounds.max {
+            return Err(Error::InvalidBet);
+        }
+
+        let waiting = get_queue(&e, token.clone(), stake);
+        if waiting.as_ref().map(|ticket| &ticket.user) == Some(&user) {
+            return Err(Error::AlreadyJoined);
+        }
+
+        if commitment_used(&e, user.clone(), user_move.clone()) {
+            return Err(Error::UsedCommit);
+        }
+        put_commitment_used(&e, user.clone(), user_move.clone());
+        place_bet(&e, &token, user.clone(), stake);
+
+        let waiting = match waiting {
+            Some(ticket) => ticket,
+            None => {
+                let ticket = Ticket {
+                    user: user.clone(),
+                    commit: user_move,
+                    since: TimeStamp::current(&e),
+                };
+                put_queue(&e, token.clone(), stake, &ticket);
+                events::queued(&e, user, token, stake);
+                return Ok(None);
+            }
+        };
+        remove_queue(&e, token.clone(), stake);
+
+        let game = next_game_id(&e);
+        put_creator(&e, game, waiting.user.clone());
+        put_game_token(&e, game, token.clone());
+        put_game_bet(&e, game, stake);
+        put_variant(&e, game, Variant::Classic);
+        put_ruleset(&e, game, Variant::Classic.ruleset(&e));
+        put_rounds(&e, game, 1);
+        put_replays(&e, game, 0);
+        put_queued(&e, game);
+        store_move(
+            &e,
+            game,
+            Player::One,
+            PlayerObj::new(waiting.user.clone(), waiting.commit),
+        );
+        store_move(
+            &e,
+            game,
+            Player::Two,
+            PlayerObj::new(user.clone(), user_move),
+        );
+        put_bet_start(&e, game, TimeStamp::current(&e));
+        put_phase(&e, game, GamePhase::Committed);
+        events::created(
+            &e,
+            game,
+            waiting.user.clone(),
+            token,
+            stake,
+            Variant::Classic,
+            1,
+        );
+        events::joined(&e, game, waiting.user, Player::One, stake);
+        events::joined(&e, game, user, Player::Two, stake);
+        Ok(Some(game))
+    }
+
+    fn drop_queue(e: Env, user: Address, token: BytesN<32>, stake: i128) -> Result<(), Error> {
+        user.require_auth();
+
+        match get_queue(&e, token.clone(), stake) {
+            Some(ticket) if ticket.user == user => remove_queue(&e, token.clone(), stake),
+            _ => return Err(Error::InvalidOp),
+        }
+
+        let payout = take_fee(&e, &token, stake);
+        send_profit(&e, &token, user.clone(), payout);
+        events::unqueued(&e, user, token, payout);
+        Ok(())
+    }
+
+    fn get_queue(e: Env, token: BytesN<32>, stake: i128) -> Option<Ticket> {
+        get_queue(&e, token, stake)
+    }
+
+    fn get_stats(e: Env, user: Address) -> Stats {
+        get_stats(&e, user)
+    }
+
+    fn get_rating(e: Env, user: Address) -> i32 {
+        get_rating(&e, user)
+    }
+
+    fn new_season(
+        e: Env,
+        token: BytesN<32>,
+        start: TimeStamp,
+        end: TimeStamp,
+        prizes: Vec<u32>,
+    ) -> Result<u32, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+
+        if get_bounds(&e, token.clone()).is_none() {
+            return Err(Error::TokenNotAllowed);
+        }
+
+        let count = season::season_count(&e);
+        if start >= end || (count > 0 && start < season::get_season(&e, count - 1)?.end) {
+            return Err(Error::InvalidSeason);
+        }
+
+        if !season::valid_prizes(&prizes) {
+            return Err(Error::InvalidPrizes);
+        }
+
+        let id = season::add_season(
+            &e,
+            &Season {
+                token: token.clone(),
+                start: start.clone(),
+                end: end.clone(),
+                prizes,
+                pool: 0,
+                phase: GamePhase::Open,
+                board: Vec::new(&e),
+            },
+        );
+        events::season_created(&e, id, token, start, end);
+        Ok(id)
+    }
+
+    fn fund_pool(e: Env, season: u32, amount: i128) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        let admin = get_admin(&e);
+        admin.require_auth();
+
+        let mut s = season::get_season(&e, season)?;
+        if s.phase != GamePhase::Open {
+            return Err(Error::WrongPhase);
+        }
+
+        if amount <= 0 {
+            return Err(Error::InvalidBet);
+        }
+
+        place_bet(&e, &s.token, admin, amount);
+        s.pool += amount;
+        season::put_season(&e, season, &s);
+        events::pool_funded(&e, season, amount);
+        Ok(())
+    }
+
+    fn end_season(e: Env, season: u32) -> Result<(), Error> {
+        let mut s = season::get_season(&e, season)?;
+        if s.phase != GamePhase::Open {
+            return Err(Error::WrongPhase);
+        }
+
+        if TimeStamp::current(&e) < s.end {
+            return Err(Error::LimitNotReached);
+        }
+
+        season::pay_prizes(&e, &s, get_admin(&e));
+        s.phase = GamePhase::Settled;
+        season::put_season(&e, season, &s);
+        events::season_ended(&e, season, s.board, s.pool);
+        Ok(())
+    }
+
+    fn get_season(e: Env, season: u32) -> Result<Season, Error> {
+        season::get_season(&e, season)
+    }
+
+    fn cancel_in(e: Env, game: GameId) -> Result<u64, Error> {
+        let limit = match get_phase(&e, game)? {
+            GamePhase::Open => get_join_limit(&e),
+            GamePhase::Committed | GamePhase::Revealing | GamePhase::NextRound => get_ts_limit(&e),
+            _ => return Err(Error::WrongPhase),
+        };
+
+        let bet_start = get_bet_start_opt(&e, game).ok_or(Error::InvalidOp)?;
+        let deadline = bet_start.add(limit);
+        let now = TimeStamp::current(&e);
+        if now >= deadline {
+            Ok(0)
+        } else {
+            Ok(deadline.sub(now).0)
+        }
+    }
+
+    fn get_bounds(e: Env, token: BytesN<32>) -> Result<BetBounds, Error> {
+        get_bounds(&e, token).ok_or(Error::TokenNotAllowed)
+    }
+
+    fn get_fees(e: Env, token: BytesN<32>) -> i128 {
+        get_fee_balance(&e, token)
+    }
+
+    fn claim_fees(e: Env, token: BytesN<32>) -> Result<i128, Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        let fee_to = get_fee_to(&e);
+        fee_to.require_auth();
+
+        let fees = get_fee_balance(&e, token.clone());
+        if fees > 0 {
+            put_fee_balance(&e, token.clone(), 0);
+            send_profit(&e, &token, fee_to.clone(), fees);
+        }
+        events::fees_claimed(&e, token, fee_to, fees);
+        Ok(fees)
+    }
+
+    fn add_token(e: Env, token: BytesN<32>, min_bet: i128, max_bet: i128) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+
+        if !valid_bounds(min_bet, max_bet) {
+            return Err(Error::InvalidBet);
+        }
+
+        put_token(
+            &e,
+            token.clone(),
+            BetBounds {
+                min: min_bet,
+                max: max_bet,
+            },
+        );
+        events::token_added(&e, token, min_bet, max_bet);
+        Ok(())
+    }
+
+    fn rm_token(e: Env, token: BytesN<32>) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+        remove_token(&e, token.clone());
+        events::token_removed(&e, token);
+        Ok(())
+    }
+
+    fn set_limit(e: Env, ts_diff: TimeStamp) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+        put_ts_limit(&e, ts_diff.clone());
+        events::limit_set(&e, ts_diff);
+        Ok(())
+    }
+
+    fn pause(e: Env) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+        put_paused(&e, true);
+        events::paused(&e, true);
+        Ok(())
+    }
+
+    fn unpause(e: Env) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
+        }
+
+        get_admin(&e).require_auth();
+        put_paused(&e, false);
+        events::paused(&e, false);
+        Ok(())
+    }
+
+    fn set_admin(e: Env, new_admin: Address) -> Result<(), Error> {
+        if !game_started(&e) {
+            return Err(Error::GameNotStarted);
         }
 
-        send_profit(&e, p_obj.id, get_bet(&e) * 2);
-        remove_player(&e, Player::One);
-        remove_player(&e, Player::Two);
+        get_admin(&e).require_auth();
+        new_admin.require_auth();
+        put_admin(&e, new_admin.clone());
+        events::admin_set(&e, new_admin);
         Ok(())
     }
 }
@@ -0,0 +1,132 @@
+use crate::{Error, GameResult, Move, Player, Variant};
+
+use soroban_sdk::{contracttype, panic_with_error, Bytes, Env, Vec};
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Rules a game is evaluated with, stored with the game when it's created
+pub struct Ruleset {
+    /// moves that can be played, a move's index in here is its index in `encoding` and `beats`
+    pub moves: Vec<Move>,
+    /// what each move is hashed as in the commitments
+    pub encoding: Vec<Bytes>,
+    /// bit `j` of entry `i` is set when move `i` beats move `j`
+    pub beats: Vec<u32>,
+}
+
+impl Ruleset {
+    // `wins` lists (winner, loser) pairs, every move is encoded as `Move::as_bytes`
+    pub fn new(e: &Env, moves: &[Move], wins: &[(Move, Move)]) -> Self {
+        let mut ruleset = Ruleset {
+            moves: Vec::new(e),
+            encoding: Vec::new(e),
+            beats: Vec::new(e),
+        };
+
+        for user_move in moves {
+            ruleset.moves.push_back(*user_move);
+            ruleset.encoding.push_back(user_move.as_bytes(e));
+            ruleset.beats.push_back(0);
+        }
+
+        for (winner, loser) in wins {
+            let (i, j) = match (ruleset.index_of(*winner), ruleset.index_of(*loser)) {
+                (Some(i), Some(j)) => (i, j),
+                _ => panic_with_error!(e, Error::InvalidMove),
+            };
+            let row = ruleset.beats.get_unchecked(i).unwrap();
+            ruleset.beats.set(i, row | (1 << j));
+        }
+
+        ruleset
+    }
+
+    pub fn index_of(&self, user_move: Move) -> Option<u32> {
+        self.moves.first_index_of(user_move)
+    }
+
+    // what `user_move` is hashed as, None if it can't be played
+    pub fn encode(&self, user_move: Move) -> Option<Bytes> {
+        self.index_of(user_move)
+            .map(|i| self.encoding.get_unchecked(i).unwrap())
+    }
+
+    pub fn beats(&self, one: Move, two: Move) -> bool {
+        match (self.index_of(one), self.index_of(two)) {
+            (Some(i), Some(j)) => (self.beats.get_unchecked(i).unwrap() >> j) & 1 == 1,
+            _ => false,
+        }
+    }
+
+    // the move beating every other move in `moves`, None if there isn't one or if they're all
+    // the same
+    pub fn top_move(&self, moves: &Vec<Move>) -> Option<Move> {
+        for candidate in moves.iter() {
+            let candidate = candidate.unwrap();
+            let mut others = false;
+            let mut beats_all = true;
+            for other in moves.iter() {
+                let other = other.unwrap();
+                if other != candidate {
+                    others = true;
+                    beats_all &= self.beats(candidate, other);
+                }
+            }
+
+            if others && beats_all {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    // result of a round where player one played `one` and player two `two`
+    pub fn result(&self, one: Move, two: Move) -> GameResult {
+        if self.beats(one, two) {
+            GameResult::Winner(Player::One)
+        } else if self.beats(two, one) {
+            GameResult::Winner(Player::Two)
+        } else {
+            GameResult::Draw
+        }
+    }
+}
+
+impl Variant {
+    // new variants only need their ruleset here, settlement goes through `Ruleset::result`
+    pub fn ruleset(&self, e: &Env) -> Ruleset {
+        match self {
+            Variant::Classic => Ruleset::new(
+                e,
+                &[Move::Rock, Move::Paper, Move::Scissors],
+                &[
+                    (Move::Rock, Move::Scissors),
+                    (Move::Paper, Move::Rock),
+                    (Move::Scissors, Move::Paper),
+                ],
+            ),
+            Variant::Rpsls => Ruleset::new(
+                e,
+                &[
+                    Move::Rock,
+                    Move::Paper,
+                    Move::Scissors,
+                    Move::Lizard,
+                    Move::Spock,
+                ],
+                &[
+                    (Move::Rock, Move::Scissors),
+                    (Move::Rock, Move::Lizard),
+                    (Move::Paper, Move::Rock),
+                    (Move::Paper, Move::Spock),
+                    (Move::Scissors, Move::Paper),
+                    (Move::Scissors, Move::Lizard),
+                    (Move::Lizard, Move::Paper),
+                    (Move::Lizard, Move::Spock),
+                    (Move::Spock, Move::Rock),
+                    (Move::Spock, Move::Scissors),
+                ],
+            ),
+        }
+    }
+}
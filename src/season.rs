@@ -0,0 +1,172 @@
+use crate::{send_profit, DataKey, Error, GamePhase, TimeStamp};
+
+use soroban_sdk::{contracttype, Address, BytesN, Env, Vec};
+
+// number of players kept on the leaderboard of a season
+pub(crate) const BOARD_SIZE: u32 = 10;
+
+#[contracttype]
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// A player's place on the leaderboard of a season
+pub struct Standing {
+    pub player: Address,
+    /// payouts received minus bets placed during the season
+    pub net: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+/// A season of play in one token, returned by `get_season`
+pub struct Season {
+    pub token: BytesN<32>,
+    /// games and rooms settled from `start` (included) to `end` (excluded) count towards the
+    /// season
+    pub start: TimeStamp,
+    pub end: TimeStamp,
+    /// shares of the pool in basis points for the first, second, ... players of the leaderboard
+    pub prizes: Vec<u32>,
+    /// prize pool funded by the admin
+    pub pool: i128,
+    /// `Open` until the season is ended, `Settled` once its prizes are paid
+    pub phase: GamePhase,
+    /// the best players by net winnings, best first, ties going to whoever got there first.
+    /// Players get on it once they're ahead for the season and only leave it when pushed off by
+    /// a better total, losses move them down but never off
+    pub board: Vec<Standing>,
+}
+
+pub(crate) fn valid_prizes(prizes: &Vec<u32>) -> bool {
+    if prizes.is_empty() || prizes.len() > BOARD_SIZE {
+        return false;
+    }
+
+    // capping every share also keeps the total from wrapping around
+    let mut total = 0;
+    for bps in prizes.iter() {
+        let bps = bps.unwrap();
+        if bps > 10_000 {
+            return false;
+        }
+        total += bps;
+    }
+    total == 10_000
+}
+
+pub(crate) fn put_season(e: &Env, id: u32, season: &Season) {
+    let key = DataKey::Season(id);
+    e.storage().set(&key, season);
+}
+
+pub(crate) fn get_season(e: &Env, id: u32) -> Result<Season, Error> {
+    let key = DataKey::Season(id);
+    match e.storage().get(&key) {
+        Some(season) => Ok(season.unwrap()),
+        None => Err(Error::NoSuchSeason),
+    }
+}
+
+pub(crate) fn season_count(e: &Env) -> u32 {
+    let key = DataKey::Seasons;
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
+
+// seasons are numbered from 0 in the order they're created
+pub(crate) fn add_season(e: &Env, season: &Season) -> u32 {
+    let id = season_count(e);
+    e.storage().set(&DataKey::Seasons, &(id + 1));
+    put_season(e, id, season);
+    id
+}
+
+fn put_net(e: &Env, id: u32, user: Address, net: i128) {
+    let key = DataKey::SeasonNet(id, user);
+    e.storage().set(&key, &net);
+}
+
+fn get_net(e: &Env, id: u32, user: Address) -> i128 {
+    let key = DataKey::SeasonNet(id, user);
+    e.storage().get(&key).unwrap_or(Ok(0)).unwrap()
+}
+
+// the season running now, if it's played in `token`. Seasons don't overlap and are created in
+// chronological order, so the search stops at the first one that's already over
+fn current(e: &Env, token: &BytesN<32>) -> Option<(u32, Season)> {
+    let now = TimeStamp::current(e);
+    let mut id = season_count(e);
+    while id > 0 {
+        id -= 1;
+        let season = get_season(e, id).unwrap();
+        if season.end <= now {
+            return None;
+        }
+        if season.start <= now {
+            return if season.token == *token {
+                Some((id, season))
+            } else {
+                None
+            };
+        }
+    }
+    None
+}
+
+// adds what `user` won (or lost) in a game settled in `token` to the running season and moves
+// them on its leaderboard. Nobody is taken off the board on a loss, so no place ever opens up
+// for a player it doesn't remember: a player pushed off a full board gets back on by beating
+// its last entry
+pub(crate) fn add_net(e: &Env, token: &BytesN<32>, user: &Address, amount: i128) {
+    let (id, mut season) = match current(e, token) {
+        Some(current) => current,
+        None => return,
+    };
+    let net = get_net(e, id, user.clone()) + amount;
+    put_net(e, id, user.clone(), net);
+
+    let on_board = season
+        .board
+        .iter()
+        .any(|standing| standing.unwrap().player == *user);
+    let mut board = Vec::new(e);
+    let mut placed = net <= 0 && !on_board;
+    for standing in season.board.iter() {
+        let standing = standing.unwrap();
+        if standing.player == *user {
+            continue;
+        }
+        if !placed && net > standing.net {
+            board.push_back(Standing {
+                player: user.clone(),
+                net,
+            });
+            placed = true;
+        }
+        board.push_back(standing);
+    }
+    if !placed {
+        board.push_back(Standing {
+            player: user.clone(),
+            net,
+        });
+    }
+
+    season.board = board.slice(..BOARD_SIZE.min(board.len()));
+    put_season(e, id, &season);
+}
+
+// pays the pool out to the top of the leaderboard. The shares of places nobody finished in, or
+// held by players who aren't ahead anymore, and the rounding dust go back to `admin`
+pub(crate) fn pay_prizes(e: &Env, season: &Season, admin: Address) {
+    let mut paid = 0;
+    for (place, bps) in season.prizes.iter().enumerate() {
+        if let Some(standing) = season.board.get(place as u32) {
+            let standing = standing.unwrap();
+            if standing.net <= 0 {
+                continue;
+            }
+            let amount = season.pool * bps.unwrap() as i128 / 10_000;
+            send_profit(e, &season.token, standing.player, amount);
+            paid += amount;
+        }
+    }
+    send_profit(e, &season.token, admin, season.pool - paid);
+}
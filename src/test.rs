@@ -1,68 +1,2541 @@
 #![cfg(test)]
 
-use crate::{token, RockPaperScissorsContract, RockPaperScissorsContractClient, TimeStamp};
-use crate::{GameResult, Move, Player};
+use crate::{commitment, Error, GameId, GamePhase, GameResult, Move, Player, Standing, Stats};
+use crate::{
+    token, RockPaperScissorsContract, RockPaperScissorsContractClient, TimeStamp, Variant,
+    QUEUE_GAME,
+};
 
-use soroban_sdk::testutils::{Ledger, LedgerInfo};
+use soroban_sdk::testutils::Ledger;
 use soroban_sdk::{
-    serde::Serialize, testutils::Address as _, Address, Bytes, BytesN, Env, IntoVal,
+    serde::Serialize, symbol, testutils::Address as _, testutils::Events, vec, Address, Bytes,
+    BytesN, Env, IntoVal, RawVal, Vec,
 };
 
-#[test]
-fn test_rock_wins() {
+fn commit(
+    e: &Env,
+    contract: &Address,
+    game: &GameId,
+    user: &Address,
+    user_move: Move,
+    secret: &str,
+) -> BytesN<32> {
+    commitment(
+        e,
+        contract,
+        *game,
+        user,
+        user_move,
+        &Bytes::from_slice(e, secret.as_bytes()),
+    )
+}
+
+// CPU instructions a single contract call may use on the network
+const CPU_LIMIT: u64 = 40_000_000;
+
+// registers a token and the contract, leaving the contract to be initialized. The token's admin
+// is returned last, the tests also make it the contract's admin
+fn deploy() -> (
+    Env,
+    RockPaperScissorsContractClient,
+    token::Client,
+    Address,
+    Address,
+) {
     let e: Env = Default::default();
     let admin = Address::random(&e);
-    let u1 = Address::random(&e);
 
     let token_id = e.register_stellar_asset_contract(admin.clone());
     let token = token::Client::new(&e, &token_id);
 
     let contract_id = e.register_contract(None, RockPaperScissorsContract);
     let client = RockPaperScissorsContractClient::new(&e, &contract_id);
+    let contract = Address::from_contract_id(&e, &contract_id);
 
-    client.initialize(&token_id, &10, &TimeStamp(3600));
+    (e, client, token, contract, admin)
+}
+
+// `deploy` and initialize the contract with bets of 1 to 1000 in the token, an hour to reveal, a
+// day to join, no treasury, no house fee and a K-factor of 32
+fn setup() -> (
+    Env,
+    RockPaperScissorsContractClient,
+    token::Client,
+    Address,
+    Address,
+) {
+    let (e, client, token, contract, admin) = deploy();
+    client.initialize(
+        &token.contract_id,
+        &1,
+        &1000,
+        &TimeStamp(3600),
+        &TimeStamp(86400),
+        &None,
+        &0,
+        &admin,
+        &32,
+        &admin,
+    );
+    (e, client, token, contract, admin)
+}
+
+#[test]
+fn test_rock_wins() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
 
     token.mint(&admin, &admin, &10);
 
-    token.incr_allow(&admin, &Address::from_contract_id(&e, &contract_id), &10);
+    token.incr_allow(&admin, &contract, &10);
 
     token.mint(&admin, &u1, &10);
 
-    token.incr_allow(&u1, &Address::from_contract_id(&e, &contract_id), &10);
+    token.incr_allow(&u1, &contract, &10);
 
-    let mut admin_make_move_image = Bytes::new(&e);
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+
+    let mut admin_make_move_image = Bytes::from_slice(&e, b"rps-commit-v1");
+    admin_make_move_image.append(&contract.clone().serialize(&e));
+    admin_make_move_image.append(&game.serialize(&e));
     admin_make_move_image.append(&admin.clone().serialize(&e));
     admin_make_move_image.append(&Move::Rock.as_bytes(&e));
     admin_make_move_image.append(&Bytes::from_slice(&e, "mysecret".as_bytes()));
     let val = e.crypto().sha256(&admin_make_move_image);
 
-    client.make_move(&admin, &val);
+    client.make_move(&game, &admin, &val, &10);
 
-    let mut u1_make_move_image = Bytes::new(&e);
+    let mut u1_make_move_image = Bytes::from_slice(&e, b"rps-commit-v1");
+    u1_make_move_image.append(&contract.clone().serialize(&e));
+    u1_make_move_image.append(&game.serialize(&e));
     u1_make_move_image.append(&u1.clone().serialize(&e));
     u1_make_move_image.append(&Move::Scissors.as_bytes(&e));
     u1_make_move_image.append(&Bytes::from_slice(&e, "u1mysecret".as_bytes()));
     let u1_val = e.crypto().sha256(&u1_make_move_image);
 
-    client.make_move(&u1, &u1_val);
+    client.make_move(&game, &u1, &u1_val, &10);
 
     let move_pre = client.reveal(
+        &game,
         &Player::One,
         &Move::Rock,
         &Bytes::from_slice(&e, "mysecret".as_bytes()),
     );
-    matches!(move_pre, Move::Rock);
+    assert_eq!(move_pre, Move::Rock);
     let u1_move_pre = client.reveal(
+        &game,
         &Player::Two,
         &Move::Scissors,
         &Bytes::from_slice(&e, "u1mysecret".as_bytes()),
     );
-    matches!(u1_move_pre, Move::Scissors);
+    assert_eq!(u1_move_pre, Move::Scissors);
 
-    matches!(client.evaluate(), GameResult::Winner(Player::One));
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::One));
     assert_eq!(token.balance(&admin), 20);
     assert_eq!(token.balance(&u1), 0);
 }
+
+#[test]
+fn test_concurrent_games() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+
+    for user in [&admin, &u1, &u2, &u3] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game_a = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let game_b = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    assert_ne!(game_a, game_b);
+
+    client.make_move(
+        &game_a,
+        &admin,
+        &commit(&e, &contract, &game_a, &admin, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game_b,
+        &u2,
+        &commit(&e, &contract, &game_b, &u2, Move::Paper, "s2"),
+        &10,
+    );
+    client.make_move(
+        &game_a,
+        &u1,
+        &commit(&e, &contract, &game_a, &u1, Move::Scissors, "s1"),
+        &10,
+    );
+    client.make_move(
+        &game_b,
+        &u3,
+        &commit(&e, &contract, &game_b, &u3, Move::Paper, "s3"),
+        &10,
+    );
+
+    client.reveal(
+        &game_a,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game_b,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    client.reveal(
+        &game_a,
+        &Player::Two,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.reveal(
+        &game_b,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+
+    assert_eq!(client.evaluate(&game_b), GameResult::Draw);
+    assert_eq!(client.evaluate(&game_a), GameResult::Winner(Player::One));
+
+    assert_eq!(token.balance(&admin), 20);
+    assert_eq!(token.balance(&u1), 0);
+    assert_eq!(token.balance(&u2), 10);
+    assert_eq!(token.balance(&u3), 10);
+    assert_eq!(token.balance(&contract), 0);
+
+    assert_eq!(
+        client.try_make_move(
+            &GameId(2),
+            &u2,
+            &commit(&e, &contract, &GameId(2), &u2, Move::Rock, "s2"),
+            &10
+        ),
+        Err(Ok(Error::NoSuchGame))
+    );
+}
+#[test]
+fn test_wrong_phase() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    assert_eq!(client.try_evaluate(&game), Err(Ok(Error::WrongPhase)));
+
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+        &10,
+    );
+
+    // player two hasn't joined yet
+    assert_eq!(
+        client.try_reveal(
+            &game,
+            &Player::One,
+            &Move::Rock,
+            &Bytes::from_slice(&e, b"s0")
+        ),
+        Err(Ok(Error::WrongPhase))
+    );
+
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s1"),
+        &10,
+    );
+    assert_eq!(client.try_evaluate(&game), Err(Ok(Error::WrongPhase)));
+
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    assert_eq!(client.try_evaluate(&game), Err(Ok(Error::NotRevealed)));
+
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::Two));
+
+    assert_eq!(client.try_evaluate(&game), Err(Ok(Error::WrongPhase)));
+    assert_eq!(client.try_cancel(&game), Err(Ok(Error::WrongPhase)));
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &admin,
+            &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+            &10
+        ),
+        Err(Ok(Error::WrongPhase))
+    );
+}
+
+#[test]
+fn test_views() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let config = client.get_config();
+    assert_eq!(config.tokens, vec![&e, token_id.clone()]);
+    assert_eq!(client.get_bounds(&token_id).min, 1);
+    assert_eq!(client.get_bounds(&token_id).max, 1000);
+    assert_eq!(config.ts_limit, TimeStamp(3600));
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let view = client.get_game(&game);
+    assert_eq!(view.phase, GamePhase::Open);
+    assert!(view.players.is_empty());
+    assert_eq!(view.bet_start, TimeStamp(0));
+    assert_eq!(client.try_cancel_in(&game), Err(Ok(Error::InvalidOp)));
+
+    let val = commit(&e, &contract, &game, &admin, Move::Rock, "s0");
+    client.make_move(&game, &admin, &val, &10);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s1"),
+        &10,
+    );
+
+    let view = client.get_game(&game);
+    assert_eq!(view.phase, GamePhase::Committed);
+    assert_eq!(view.bet_start, TimeStamp(1000));
+    assert_eq!(view.players.len(), 2);
+
+    let p1 = client.get_player(&game, &Player::One);
+    assert_eq!(p1.id, admin);
+    assert_eq!(p1.user_move, val);
+    assert_eq!(p1.move_pre, Move::Unrevealed);
+
+    assert_eq!(client.cancel_in(&game), 3600);
+    e.ledger().with_mut(|li| li.timestamp = 2000);
+    assert_eq!(client.cancel_in(&game), 2600);
+    e.ledger().with_mut(|li| li.timestamp = 5000);
+    assert_eq!(client.cancel_in(&game), 0);
+
+    assert_eq!(client.try_get_game(&GameId(7)), Err(Ok(Error::NoSuchGame)));
+}
+
+// events published by the game contract only, the token contract publishes its own
+fn contract_events(e: &Env, contract_id: &BytesN<32>) -> Vec<(BytesN<32>, Vec<RawVal>, RawVal)> {
+    let mut events = Vec::new(e);
+    for event in e.events().all().iter() {
+        let event = event.unwrap();
+        if event.0 == *contract_id {
+            events.push_back(event);
+        }
+    }
+    events
+}
+
+#[test]
+fn test_events() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let contract_id = client.contract_id.clone();
+    let u1 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &20);
+        token.incr_allow(user, &contract, &20);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Scissors, "s1"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&game);
+
+    assert_eq!(
+        contract_events(&e, &contract_id),
+        vec![
+            &e,
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("init")).into_val(&e),
+                (
+                    token_id.clone(),
+                    1_i128,
+                    1000_i128,
+                    TimeStamp(3600),
+                    TimeStamp(86400)
+                )
+                    .into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("created")).into_val(&e),
+                (
+                    game,
+                    admin.clone(),
+                    token_id.clone(),
+                    10_i128,
+                    Variant::Classic,
+                    1_u32
+                )
+                    .into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("joined")).into_val(&e),
+                (game, admin.clone(), Player::One, 10_i128).into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("joined")).into_val(&e),
+                (game, u1.clone(), Player::Two, 10_i128).into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("revealed")).into_val(&e),
+                (game, admin.clone(), Player::One, Move::Rock).into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("revealed")).into_val(&e),
+                (game, u1.clone(), Player::Two, Move::Scissors).into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("round")).into_val(&e),
+                (game, 1_u32, GameResult::Winner(Player::One)).into_val(&e)
+            ),
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("settled")).into_val(&e),
+                (game, GameResult::Winner(Player::One), 20_i128).into_val(&e)
+            ),
+        ]
+    );
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s2"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s3"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+
+    e.ledger().with_mut(|li| li.timestamp = 4601);
+    client.cancel(&game);
+
+    let events = contract_events(&e, &contract_id);
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![
+            &e,
+            (
+                contract_id.clone(),
+                (symbol!("rps"), symbol!("cancelled")).into_val(&e),
+                (game, admin.clone(), 20_i128).into_val(&e)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_cancel_open_game() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    token.mint(&admin, &admin, &10);
+    token.incr_allow(&admin, &contract, &10);
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+
+    // nobody has committed yet, there is nothing to refund
+    assert_eq!(client.try_cancel(&game), Err(Ok(Error::InvalidOp)));
+
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+        &10,
+    );
+    assert_eq!(token.balance(&admin), 0);
+    assert_eq!(client.cancel_in(&game), 86400);
+
+    e.ledger().with_mut(|li| li.timestamp = 87399);
+    assert_eq!(client.try_cancel(&game), Err(Ok(Error::LimitNotReached)));
+
+    e.ledger().with_mut(|li| li.timestamp = 87400);
+    client.cancel(&game);
+
+    assert_eq!(token.balance(&admin), 10);
+    assert_eq!(token.balance(&contract), 0);
+    assert_eq!(client.get_game(&game).phase, GamePhase::Cancelled);
+}
+
+#[test]
+fn test_cancel_nobody_revealed() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s1"),
+        &10,
+    );
+
+    assert_eq!(client.try_cancel(&game), Err(Ok(Error::LimitNotReached)));
+
+    e.ledger().with_mut(|li| li.timestamp = 4600);
+    client.cancel(&game);
+
+    assert_eq!(token.balance(&admin), 10);
+    assert_eq!(token.balance(&u1), 10);
+    assert_eq!(token.balance(&contract), 0);
+    assert_eq!(client.get_game(&game).phase, GamePhase::Cancelled);
+}
+
+#[test]
+fn test_cancel_nobody_revealed_treasury() {
+    let (e, client, token, contract, admin) = deploy();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let treasury = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    client.initialize(
+        &token_id,
+        &1,
+        &1000,
+        &TimeStamp(3600),
+        &TimeStamp(86400),
+        &Some(treasury.clone()),
+        &0,
+        &admin,
+        &32,
+        &admin,
+    );
+    assert_eq!(client.get_config().treasury, vec![&e, treasury.clone()]);
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s1"),
+        &10,
+    );
+
+    e.ledger().with_mut(|li| li.timestamp = 4600);
+    client.cancel(&game);
+
+    assert_eq!(token.balance(&admin), 0);
+    assert_eq!(token.balance(&u1), 0);
+    assert_eq!(token.balance(&treasury), 20);
+    assert_eq!(token.balance(&contract), 0);
+}
+
+#[test]
+fn test_fees() {
+    let (e, client, token, contract, admin) = deploy();
+    let token_id = token.contract_id.clone();
+    let house = Address::random(&e);
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    assert_eq!(
+        client.try_initialize(
+            &token_id,
+            &1,
+            &1000,
+            &TimeStamp(3600),
+            &TimeStamp(86400),
+            &None,
+            &10_001,
+            &house,
+            &32,
+            &admin
+        ),
+        Err(Ok(Error::InvalidFee))
+    );
+
+    // 5% house fee
+    client.initialize(
+        &token_id,
+        &1,
+        &1000,
+        &TimeStamp(3600),
+        &TimeStamp(86400),
+        &None,
+        &500,
+        &house,
+        &32,
+        &admin,
+    );
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &200);
+        token.incr_allow(user, &contract, &200);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &100, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &100,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Scissors, "s1"),
+        &100,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&game);
+
+    assert_eq!(token.balance(&u1), 290);
+    assert_eq!(token.balance(&u2), 100);
+    assert_eq!(client.get_fees(&token_id), 10);
+
+    let game = client.new_game(&admin, &None, &token_id, &100, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s2"),
+        &100,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s3"),
+        &100,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    client.evaluate(&game);
+
+    assert_eq!(token.balance(&u1), 285);
+    assert_eq!(token.balance(&u2), 95);
+    assert_eq!(client.get_fees(&token_id), 20);
+    assert_eq!(token.balance(&contract), 20);
+
+    assert_eq!(client.claim_fees(&token_id), 20);
+    assert_eq!(token.balance(&house), 20);
+    assert_eq!(token.balance(&contract), 0);
+    assert_eq!(client.get_fees(&token_id), 0);
+}
+
+#[test]
+fn test_admin() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let new_admin = Address::random(&e);
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.add_token(&token_id, &20, &50);
+    client.set_limit(&TimeStamp(60));
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &10,
+    );
+    assert_eq!(token.balance(&u1), 90);
+    assert_eq!(client.get_game(&game).bet, 10);
+    assert_eq!(client.get_bounds(&token_id).min, 20);
+    assert_eq!(client.get_bounds(&token_id).max, 50);
+    assert_eq!(client.get_config().ts_limit, TimeStamp(60));
+    assert_eq!(
+        client.try_add_token(&token_id, &50, &20),
+        Err(Ok(Error::InvalidBet))
+    );
+
+    client.pause();
+    assert!(client.get_config().paused);
+    assert_eq!(
+        client.try_new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0),
+        Err(Ok(Error::Paused))
+    );
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &u2,
+            &commit(&e, &contract, &game, &u2, Move::Paper, "s1"),
+            &10
+        ),
+        Err(Ok(Error::Paused))
+    );
+
+    client.unpause();
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s1"),
+        &10,
+    );
+
+    assert_eq!(
+        client.try_new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0),
+        Err(Ok(Error::InvalidBet))
+    );
+    let game = client.new_game(&admin, &None, &token_id, &50, &Variant::Classic, &1, &0);
+    assert_eq!(client.get_game(&game).bet, 50);
+
+    client.set_admin(&new_admin);
+    assert_eq!(client.get_config().admin, new_admin);
+}
+
+#[test]
+fn test_replayed_commitment() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+
+    for user in [&admin, &u1] {
+        token.mint(&admin, user, &30);
+        token.incr_allow(user, &contract, &30);
+    }
+
+    let game = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let val = commit(&e, &contract, &game, &admin, Move::Paper, "mysecret");
+    client.make_move(&game, &admin, &val, &10);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "u1mysecret"),
+        &10,
+    );
+
+    // the same commitment can't be submitted again, not even in another game
+    let replay = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    assert_eq!(
+        client.try_make_move(&replay, &admin, &val, &10),
+        Err(Ok(Error::UsedCommit))
+    );
+
+    // commitments built for another game don't reveal in this one
+    let other = commit(&e, &contract, &replay, &admin, Move::Paper, "mysecret");
+    client.make_move(&replay, &admin, &other, &10);
+    client.make_move(
+        &replay,
+        &u1,
+        &commit(&e, &contract, &replay, &u1, Move::Rock, "x"),
+        &10,
+    );
+    assert_eq!(
+        client.try_reveal(
+            &game,
+            &Player::Two,
+            &Move::Rock,
+            &Bytes::from_slice(&e, b"x")
+        ),
+        Err(Ok(Error::InvalidReveal))
+    );
+
+    // commitments are registered per player: copying somebody else's doesn't use it up for them
+    let third = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let fourth = client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let pending = commit(&e, &contract, &fourth, &admin, Move::Rock, "pending");
+    client.make_move(&third, &u1, &pending, &10);
+    client.make_move(&fourth, &admin, &pending, &10);
+}
+
+#[test]
+fn test_join_rules() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+
+    for user in [&u1, &u2, &u3] {
+        token.mint(&admin, user, &20);
+        token.incr_allow(user, &contract, &20);
+    }
+
+    // the same address can't take both slots
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &10,
+    );
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &u1,
+            &commit(&e, &contract, &game, &u1, Move::Paper, "s1"),
+            &10
+        ),
+        Err(Ok(Error::AlreadyJoined))
+    );
+
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.set_lists(
+        &game,
+        &vec![&e, u1.clone(), u2.clone(), u3.clone()],
+        &vec![&e, u3.clone()],
+    );
+    let view = client.get_game(&game);
+    assert_eq!(view.creator, u1);
+    assert_eq!(view.allow.len(), 3);
+    assert_eq!(view.deny, vec![&e, u3.clone()]);
+
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &admin,
+            &commit(&e, &contract, &game, &admin, Move::Rock, "s2"),
+            &10
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &u3,
+            &commit(&e, &contract, &game, &u3, Move::Rock, "s3"),
+            &10
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Rock, "s4"),
+        &10,
+    );
+}
+
+#[test]
+fn test_private_game() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let friend = Address::random(&e);
+    let sniper = Address::random(&e);
+
+    for user in [&admin, &friend, &sniper] {
+        token.mint(&admin, user, &10);
+        token.incr_allow(user, &contract, &10);
+    }
+
+    let game = client.new_game(
+        &admin,
+        &Some(friend.clone()),
+        &token_id,
+        &10,
+        &Variant::Classic,
+        &1,
+        &0,
+    );
+    let view = client.get_game(&game);
+    assert!(view.private);
+    assert_eq!(view.opponent, vec![&e, friend.clone()]);
+    assert!(
+        !client
+            .get_game(&client.new_game(&admin, &None, &token_id, &10, &Variant::Classic, &1, &0))
+            .private
+    );
+    assert_eq!(
+        client.try_new_game(
+            &admin,
+            &Some(admin.clone()),
+            &token_id,
+            &10,
+            &Variant::Classic,
+            &1,
+            &0
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+
+    // the first slot belongs to the creator
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &sniper,
+            &commit(&e, &contract, &game, &sniper, Move::Rock, "s0"),
+            &10
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+    client.make_move(
+        &game,
+        &admin,
+        &commit(&e, &contract, &game, &admin, Move::Rock, "s1"),
+        &10,
+    );
+
+    // and the second one to the invited opponent
+    assert_eq!(
+        client.try_make_move(
+            &game,
+            &sniper,
+            &commit(&e, &contract, &game, &sniper, Move::Rock, "s2"),
+            &10
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+    client.make_move(
+        &game,
+        &friend,
+        &commit(&e, &contract, &game, &friend, Move::Paper, "s3"),
+        &10,
+    );
+    assert_eq!(client.get_game(&game).phase, GamePhase::Committed);
+}
+
+#[test]
+fn test_game_bets() {
+    let (e, client, token, contract, admin) = deploy();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    client.initialize(
+        &token_id,
+        &5,
+        &100,
+        &TimeStamp(3600),
+        &TimeStamp(86400),
+        &None,
+        &0,
+        &admin,
+        &32,
+        &admin,
+    );
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    assert_eq!(
+        client.try_new_game(&u1, &None, &token_id, &4, &Variant::Classic, &1, &0),
+        Err(Ok(Error::InvalidBet))
+    );
+    assert_eq!(
+        client.try_new_game(&u1, &None, &token_id, &101, &Variant::Classic, &1, &0),
+        Err(Ok(Error::InvalidBet))
+    );
+
+    let game = client.new_game(&u1, &None, &token_id, &40, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &40,
+    );
+
+    // the joiner has to accept exactly the game's bet
+    let u2_val = commit(&e, &contract, &game, &u2, Move::Paper, "s1");
+    assert_eq!(
+        client.try_make_move(&game, &u2, &u2_val, &10),
+        Err(Ok(Error::InvalidBet))
+    );
+    client.make_move(&game, &u2, &u2_val, &40);
+    assert_eq!(token.balance(&contract), 80);
+
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&game);
+
+    assert_eq!(token.balance(&u1), 60);
+    assert_eq!(token.balance(&u2), 140);
+}
+
+#[test]
+fn test_tokens() {
+    let (e, client, token, contract, admin) = deploy();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    let other_id = e.register_stellar_asset_contract(admin.clone());
+    let other = token::Client::new(&e, &other_id);
+    let unlisted_id = e.register_stellar_asset_contract(admin.clone());
+
+    client.initialize(
+        &token_id,
+        &1,
+        &1000,
+        &TimeStamp(3600),
+        &TimeStamp(86400),
+        &None,
+        &500,
+        &admin,
+        &32,
+        &admin,
+    );
+    client.add_token(&other_id, &100, &500);
+    assert_eq!(
+        client.get_config().tokens,
+        vec![&e, token_id.clone(), other_id.clone()]
+    );
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &1000);
+        token.incr_allow(user, &contract, &1000);
+        other.mint(&admin, user, &1000);
+        other.incr_allow(user, &contract, &1000);
+    }
+
+    assert_eq!(
+        client.try_new_game(&u1, &None, &unlisted_id, &10, &Variant::Classic, &1, &0),
+        Err(Ok(Error::TokenNotAllowed))
+    );
+    assert_eq!(
+        client.try_new_game(&u1, &None, &other_id, &10, &Variant::Classic, &1, &0),
+        Err(Ok(Error::InvalidBet))
+    );
+
+    let game = client.new_game(&u1, &None, &other_id, &200, &Variant::Classic, &1, &0);
+    assert_eq!(client.get_game(&game).token, other_id);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &200,
+    );
+
+    // delisting the token doesn't strand running games
+    client.rm_token(&other_id);
+    assert_eq!(client.get_config().tokens, vec![&e, token_id.clone()]);
+    assert_eq!(
+        client.try_get_bounds(&other_id),
+        Err(Ok(Error::TokenNotAllowed))
+    );
+
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s1"),
+        &200,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&game);
+
+    assert_eq!(other.balance(&u1), 800);
+    assert_eq!(other.balance(&u2), 1180);
+    assert_eq!(token.balance(&u1), 1000);
+    assert_eq!(client.get_fees(&other_id), 20);
+    assert_eq!(client.get_fees(&token_id), 0);
+
+    assert_eq!(client.claim_fees(&other_id), 20);
+    assert_eq!(other.balance(&admin), 20);
+    assert_eq!(other.balance(&contract), 0);
+}
+
+#[test]
+fn test_series() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    assert_eq!(
+        client.try_new_game(&u1, &None, &token_id, &10, &Variant::Classic, &2, &0),
+        Err(Ok(Error::InvalidRounds))
+    );
+    assert_eq!(
+        client.try_new_game(&u1, &None, &token_id, &10, &Variant::Classic, &9, &0),
+        Err(Ok(Error::InvalidRounds))
+    );
+
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &3, &1);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "r1a"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Scissors, "r1b"),
+        &10,
+    );
+
+    let rounds = [
+        (Move::Rock, Move::Scissors, GameResult::Winner(Player::One)),
+        (Move::Rock, Move::Paper, GameResult::Winner(Player::Two)),
+        (Move::Paper, Move::Paper, GameResult::Draw),
+    ];
+    for (i, (m1, m2, result)) in rounds.iter().enumerate() {
+        let (s1, s2) = (["r1a", "r2a", "r3a"][i], ["r1b", "r2b", "r3b"][i]);
+        if i > 0 {
+            client.next_move(&game, &u1, &commit(&e, &contract, &game, &u1, *m1, s1));
+            assert_eq!(
+                client.try_next_move(&game, &u1, &commit(&e, &contract, &game, &u1, *m1, "x")),
+                Err(Ok(Error::AlreadyCommitted))
+            );
+            assert_eq!(client.get_game(&game).phase, GamePhase::NextRound);
+            client.next_move(&game, &u2, &commit(&e, &contract, &game, &u2, *m2, s2));
+        }
+
+        client.reveal(
+            &game,
+            &Player::One,
+            m1,
+            &Bytes::from_slice(&e, s1.as_bytes()),
+        );
+        client.reveal(
+            &game,
+            &Player::Two,
+            m2,
+            &Bytes::from_slice(&e, s2.as_bytes()),
+        );
+        assert_eq!(client.evaluate(&game), *result);
+        assert_eq!(client.get_game(&game).results.len(), i as u32 + 1);
+
+        // nothing is paid out before the series is decided
+        assert_eq!(token.balance(&contract), 20);
+    }
+
+    // the drawn round was replayed, the series goes on past it
+    assert_eq!(client.get_game(&game).replayed, 1);
+    let stranger = Address::random(&e);
+    assert_eq!(
+        client.try_next_move(
+            &game,
+            &stranger,
+            &commit(&e, &contract, &game, &stranger, Move::Rock, "s")
+        ),
+        Err(Ok(Error::NotAllowed))
+    );
+
+    client.next_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "r4a"),
+    );
+    client.next_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Rock, "r4b"),
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"r4a"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"r4b"),
+    );
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::One));
+
+    let view = client.get_game(&game);
+    assert_eq!(view.phase, GamePhase::Settled);
+    assert_eq!(view.results.len(), 4);
+    assert_eq!(token.balance(&u1), 110);
+    assert_eq!(token.balance(&u2), 90);
+}
+
+#[test]
+fn test_series_cancel() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &3, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s1"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&game);
+
+    // player one lost the first round and doesn't commit to the second one
+    e.ledger().with_mut(|li| li.timestamp = 2000);
+    client.next_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Rock, "s2"),
+    );
+    assert_eq!(client.cancel_in(&game), 2600);
+    assert_eq!(client.try_cancel(&game), Err(Ok(Error::LimitNotReached)));
+
+    e.ledger().with_mut(|li| li.timestamp = 4600);
+    client.cancel(&game);
+
+    assert_eq!(client.get_game(&game).phase, GamePhase::Cancelled);
+    assert_eq!(token.balance(&u1), 90);
+    assert_eq!(token.balance(&u2), 110);
+}
+
+#[test]
+fn test_sudden_death() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    assert_eq!(
+        client.try_new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &11),
+        Err(Ok(Error::InvalidRounds))
+    );
+
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &2);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Rock, "s1"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+
+    // the draw isn't refunded, both players commit again
+    assert_eq!(client.evaluate(&game), GameResult::Draw);
+    assert_eq!(client.get_game(&game).phase, GamePhase::NextRound);
+    assert_eq!(token.balance(&contract), 20);
+
+    client.next_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Paper, "s2"),
+    );
+    client.next_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Scissors, "s3"),
+    );
+    assert_eq!(client.get_game(&game).phase, GamePhase::Committed);
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::Two));
+
+    let view = client.get_game(&game);
+    assert_eq!(view.phase, GamePhase::Settled);
+    assert_eq!(view.replayed, 1);
+    assert_eq!(token.balance(&u1), 90);
+    assert_eq!(token.balance(&u2), 110);
+
+    // once the replays are used up a draw is refunded
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &1);
+    for (i, secrets) in [("s4", "s5"), ("s6", "s7")].iter().enumerate() {
+        let u1_val = commit(&e, &contract, &game, &u1, Move::Paper, secrets.0);
+        let u2_val = commit(&e, &contract, &game, &u2, Move::Paper, secrets.1);
+        if i == 0 {
+            client.make_move(&game, &u1, &u1_val, &10);
+            client.make_move(&game, &u2, &u2_val, &10);
+        } else {
+            client.next_move(&game, &u1, &u1_val);
+            client.next_move(&game, &u2, &u2_val);
+        }
+        client.reveal(
+            &game,
+            &Player::One,
+            &Move::Paper,
+            &Bytes::from_slice(&e, secrets.0.as_bytes()),
+        );
+        client.reveal(
+            &game,
+            &Player::Two,
+            &Move::Paper,
+            &Bytes::from_slice(&e, secrets.1.as_bytes()),
+        );
+        assert_eq!(client.evaluate(&game), GameResult::Draw);
+    }
+
+    assert_eq!(client.get_game(&game).phase, GamePhase::Settled);
+    assert_eq!(token.balance(&u1), 90);
+    assert_eq!(token.balance(&u2), 110);
+    assert_eq!(token.balance(&contract), 0);
+}
+
+#[test]
+fn test_rpsls_outcomes() {
+    let e: Env = Default::default();
+    let rpsls = Variant::Rpsls.ruleset(&e);
+    let classic = Variant::Classic.ruleset(&e);
+    assert_eq!(rpsls.moves.len(), 5);
+    assert_eq!(classic.moves.len(), 3);
+
+    let moves = [
+        Move::Rock,
+        Move::Paper,
+        Move::Scissors,
+        Move::Lizard,
+        Move::Spock,
+    ];
+    // row: player one's move, column: player two's move, in the order above.
+    // 0 is a draw, 1 and 2 the winning player
+    let expected = [
+        [0, 2, 1, 1, 2],
+        [1, 0, 2, 2, 1],
+        [2, 1, 0, 1, 2],
+        [2, 1, 2, 0, 1],
+        [1, 2, 1, 2, 0],
+    ];
+
+    for (i, one) in moves.iter().enumerate() {
+        for (j, two) in moves.iter().enumerate() {
+            let result = match expected[i][j] {
+                1 => GameResult::Winner(Player::One),
+                2 => GameResult::Winner(Player::Two),
+                _ => GameResult::Draw,
+            };
+            assert_eq!(
+                rpsls.result(*one, *two),
+                result,
+                "{:?} against {:?}",
+                one,
+                two
+            );
+        }
+    }
+
+    // the classic variant still follows the original rule
+    for one in &moves[..3] {
+        for two in &moves[..3] {
+            let result = if one == two {
+                GameResult::Draw
+            } else if (one.repr() + 1) % 3 == two.repr() {
+                GameResult::Winner(Player::Two)
+            } else {
+                GameResult::Winner(Player::One)
+            };
+            assert_eq!(classic.result(*one, *two), result);
+        }
+    }
+}
+
+#[test]
+fn test_rpsls_game() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    // lizard and Spock can't be revealed in a classic game
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Spock, "s0"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Rock, "s1"),
+        &10,
+    );
+    assert_eq!(
+        client.try_reveal(
+            &game,
+            &Player::One,
+            &Move::Spock,
+            &Bytes::from_slice(&e, b"s0")
+        ),
+        Err(Ok(Error::InvalidMove))
+    );
+
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Rpsls, &1, &0);
+    assert_eq!(client.get_game(&game).variant, Variant::Rpsls);
+    assert_eq!(client.get_game(&game).ruleset, Variant::Rpsls.ruleset(&e));
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Spock, "s2"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Lizard, "s3"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Spock,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Lizard,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::Two));
+
+    assert_eq!(token.balance(&u1), 80);
+    assert_eq!(token.balance(&u2), 100);
+}
+
+#[test]
+fn test_room() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+    let u4 = Address::random(&e);
+
+    for user in [&u1, &u2, &u3, &u4] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    for seats in [2, 7] {
+        assert_eq!(
+            client.try_new_room(&u1, &token_id, &10, &Variant::Classic, &seats),
+            Err(Ok(Error::InvalidSeats))
+        );
+    }
+
+    // rock beats the only other move played, both rock players split the pot
+    let room = client.new_room(&u1, &token_id, &10, &Variant::Classic, &3);
+    let players = [
+        (&u1, Move::Rock, "s0"),
+        (&u2, Move::Rock, "s1"),
+        (&u3, Move::Scissors, "s2"),
+    ];
+    for (user, user_move, secret) in players.iter() {
+        client.join_room(
+            &room,
+            user,
+            &commit(&e, &contract, &room, user, *user_move, secret),
+            &10,
+        );
+    }
+    assert_eq!(
+        client.try_join_room(
+            &room,
+            &u4,
+            &commit(&e, &contract, &room, &u4, Move::Paper, "s3"),
+            &10
+        ),
+        Err(Ok(Error::WrongPhase))
+    );
+    // rooms share ids with games but can't be played as one
+    assert_eq!(
+        client.try_make_move(
+            &room,
+            &u4,
+            &commit(&e, &contract, &room, &u4, Move::Paper, "s3"),
+            &10
+        ),
+        Err(Ok(Error::NoSuchGame))
+    );
+    assert_eq!(client.get_room(&room).phase, GamePhase::Committed);
+
+    assert_eq!(client.try_eval_room(&room), Err(Ok(Error::WrongPhase)));
+    for (seat, (_, user_move, secret)) in players.iter().enumerate() {
+        client.reveal_at(
+            &room,
+            &(seat as u32),
+            user_move,
+            &Bytes::from_slice(&e, secret.as_bytes()),
+        );
+        if seat < 2 {
+            assert_eq!(client.try_eval_room(&room), Err(Ok(Error::NotRevealed)));
+        }
+    }
+
+    assert_eq!(client.eval_room(&room), vec![&e, u1.clone(), u2.clone()]);
+    assert_eq!(client.get_room(&room).phase, GamePhase::Settled);
+    assert_eq!(token.balance(&u1), 105);
+    assert_eq!(token.balance(&u2), 105);
+    assert_eq!(token.balance(&u3), 90);
+
+    // all three moves played, nobody beats everybody else so the pot is split between all
+    let room = client.new_room(&u1, &token_id, &10, &Variant::Classic, &3);
+    let players = [
+        (&u1, Move::Rock, "s4"),
+        (&u2, Move::Paper, "s5"),
+        (&u3, Move::Scissors, "s6"),
+    ];
+    for (user, user_move, secret) in players.iter() {
+        client.join_room(
+            &room,
+            user,
+            &commit(&e, &contract, &room, user, *user_move, secret),
+            &10,
+        );
+    }
+    for (seat, (_, user_move, secret)) in players.iter().enumerate() {
+        client.reveal_at(
+            &room,
+            &(seat as u32),
+            user_move,
+            &Bytes::from_slice(&e, secret.as_bytes()),
+        );
+    }
+
+    assert_eq!(
+        client.eval_room(&room),
+        vec![&e, u1.clone(), u2.clone(), u3.clone()]
+    );
+    assert_eq!(token.balance(&u1), 105);
+    assert_eq!(token.balance(&u2), 105);
+    assert_eq!(token.balance(&u3), 90);
+    assert_eq!(token.balance(&contract), 0);
+}
+
+#[test]
+fn test_room_budget() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+
+    // a running season has every player recorded on its board too
+    client.new_season(
+        &token_id,
+        &TimeStamp(0),
+        &TimeStamp(1000),
+        &vec![&e, 10_000],
+    );
+
+    // all three moves are played, so the pot is split and every player gets paid
+    let room = client.new_room(&admin, &token_id, &10, &Variant::Classic, &6);
+    let moves = [Move::Rock, Move::Paper, Move::Scissors];
+    for seat in 0..6 {
+        let user = Address::random(&e);
+        token.mint(&admin, &user, &10);
+        token.incr_allow(&user, &contract, &10);
+        client.join_room(
+            &room,
+            &user,
+            &commit(&e, &contract, &room, &user, moves[seat % 3], "s"),
+            &10,
+        );
+    }
+    for seat in 0..6 {
+        client.reveal_at(
+            &room,
+            &(seat as u32),
+            &moves[seat % 3],
+            &Bytes::from_slice(&e, b"s"),
+        );
+    }
+
+    e.budget().reset();
+    assert_eq!(client.eval_room(&room).len(), 6);
+    assert!(e.budget().cpu_instruction_cost() < CPU_LIMIT);
+}
+
+#[test]
+fn test_drop_room() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    for user in [&u1, &u2, &u3] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    // the room doesn't fill up in time, the players who joined are refunded
+    let room = client.new_room(&u1, &token_id, &10, &Variant::Classic, &3);
+    client.join_room(
+        &room,
+        &u1,
+        &commit(&e, &contract, &room, &u1, Move::Rock, "s0"),
+        &10,
+    );
+    assert_eq!(client.try_drop_room(&room), Err(Ok(Error::LimitNotReached)));
+
+    e.ledger().with_mut(|li| li.timestamp = 1000 + 86400);
+    client.drop_room(&room);
+    assert_eq!(client.get_room(&room).phase, GamePhase::Cancelled);
+    assert_eq!(token.balance(&u1), 100);
+
+    // one player doesn't reveal, the room is settled between the other two
+    let room = client.new_room(&u1, &token_id, &9, &Variant::Classic, &3);
+    let players = [
+        (&u1, Move::Paper, "s1"),
+        (&u2, Move::Rock, "s2"),
+        (&u3, Move::Scissors, "s3"),
+    ];
+    for (user, user_move, secret) in players.iter() {
+        client.join_room(
+            &room,
+            user,
+            &commit(&e, &contract, &room, user, *user_move, secret),
+            &9,
+        );
+    }
+    for (seat, (_, user_move, secret)) in players.iter().take(2).enumerate() {
+        client.reveal_at(
+            &room,
+            &(seat as u32),
+            user_move,
+            &Bytes::from_slice(&e, secret.as_bytes()),
+        );
+    }
+    assert_eq!(client.try_drop_room(&room), Err(Ok(Error::LimitNotReached)));
+
+    e.ledger().with_mut(|li| li.timestamp = 1000 + 86400 + 3600);
+    client.drop_room(&room);
+
+    let room = client.get_room(&room);
+    assert_eq!(room.phase, GamePhase::Cancelled);
+    assert_eq!(room.winners, vec![&e, u1.clone()]);
+    assert_eq!(token.balance(&u1), 118);
+    assert_eq!(token.balance(&u2), 91);
+    assert_eq!(token.balance(&u3), 91);
+}
+
+#[test]
+fn test_tournament() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+    let u4 = Address::random(&e);
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+
+    for user in [&u1, &u2, &u3, &u4] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    let prizes = vec![&e, 7000_u32, 2000, 1000];
+    for size in [6, 16] {
+        assert_eq!(
+            client.try_new_tourn(&admin, &token_id, &10, &Variant::Classic, &size, &prizes),
+            Err(Ok(Error::InvalidSeats))
+        );
+    }
+    assert_eq!(
+        client.try_new_tourn(
+            &admin,
+            &token_id,
+            &10,
+            &Variant::Classic,
+            &4,
+            &vec![&e, 5000_u32]
+        ),
+        Err(Ok(Error::InvalidPrizes))
+    );
+    // the shares would wrap around to 10000 when added as u32
+    assert_eq!(
+        client.try_new_tourn(
+            &admin,
+            &token_id,
+            &10,
+            &Variant::Classic,
+            &4,
+            &vec![&e, u32::MAX, 10_001]
+        ),
+        Err(Ok(Error::InvalidPrizes))
+    );
+
+    let tourn = client.new_tourn(&admin, &token_id, &10, &Variant::Classic, &4, &prizes);
+    for user in [&u1, &u2, &u3, &u4] {
+        client.join_tourn(&tourn, user);
+        if *user == u1 {
+            assert_eq!(
+                client.try_join_tourn(&tourn, &u1),
+                Err(Ok(Error::AlreadyJoined))
+            );
+        }
+    }
+    assert_eq!(token.balance(&contract), 40);
+
+    // seeds 1 and 4 play each other, and seeds 2 and 3. The final is only created once both are
+    // over
+    let view = client.get_tourn(&tourn);
+    assert_eq!(view.phase, GamePhase::Committed);
+    assert_eq!(view.matches.len(), 3);
+    let last = view.matches.get_unchecked(0).unwrap();
+    let semi_a = view.matches.get_unchecked(1).unwrap();
+    let semi_b = view.matches.get_unchecked(2).unwrap();
+    assert_eq!(
+        client.get_game(&semi_a).allow,
+        vec![&e, u1.clone(), u4.clone()]
+    );
+    assert_eq!(
+        client.get_game(&semi_b).allow,
+        vec![&e, u2.clone(), u3.clone()]
+    );
+    assert_eq!(
+        client.try_set_lists(&semi_a, &Vec::new(&e), &Vec::new(&e)),
+        Err(Ok(Error::NotAllowed))
+    );
+
+    client.make_move(
+        &semi_a,
+        &u4,
+        &commit(&e, &contract, &semi_a, &u4, Move::Scissors, "s0"),
+        &0,
+    );
+    client.make_move(
+        &semi_a,
+        &u1,
+        &commit(&e, &contract, &semi_a, &u1, Move::Rock, "s1"),
+        &0,
+    );
+    client.reveal(
+        &semi_a,
+        &Player::One,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &semi_a,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    client.evaluate(&semi_a);
+    assert_eq!(client.try_get_game(&last), Err(Ok(Error::NoSuchGame)));
+
+    // seed 3 never shows up, seed 2 goes through once the join limit is over
+    client.make_move(
+        &semi_b,
+        &u2,
+        &commit(&e, &contract, &semi_b, &u2, Move::Paper, "s2"),
+        &0,
+    );
+    e.ledger().with_mut(|li| li.timestamp = 1000 + 86400);
+    client.cancel(&semi_b);
+
+    assert_eq!(
+        client.get_game(&last).allow,
+        vec![&e, u1.clone(), u2.clone()]
+    );
+
+    // the final is drawn once, then replayed
+    client.make_move(
+        &last,
+        &u1,
+        &commit(&e, &contract, &last, &u1, Move::Rock, "s3"),
+        &0,
+    );
+    client.make_move(
+        &last,
+        &u2,
+        &commit(&e, &contract, &last, &u2, Move::Rock, "s4"),
+        &0,
+    );
+    client.reveal(
+        &last,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    client.reveal(
+        &last,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s4"),
+    );
+    assert_eq!(client.evaluate(&last), GameResult::Draw);
+
+    client.next_move(
+        &last,
+        &u1,
+        &commit(&e, &contract, &last, &u1, Move::Rock, "s5"),
+    );
+    client.next_move(
+        &last,
+        &u2,
+        &commit(&e, &contract, &last, &u2, Move::Paper, "s6"),
+    );
+    client.reveal(
+        &last,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s5"),
+    );
+    client.reveal(
+        &last,
+        &Player::Two,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s6"),
+    );
+    client.evaluate(&last);
+
+    let view = client.get_tourn(&tourn);
+    assert_eq!(view.phase, GamePhase::Settled);
+    assert_eq!(
+        view.ranking,
+        vec![&e, u2.clone(), u1.clone(), u4.clone(), u3.clone()]
+    );
+    assert_eq!(token.balance(&u2), 118);
+    assert_eq!(token.balance(&u1), 98);
+    assert_eq!(token.balance(&u3), 92);
+    assert_eq!(token.balance(&u4), 92);
+    assert_eq!(token.balance(&contract), 0);
+
+    // a tournament that doesn't fill up is refunded
+    let tourn = client.new_tourn(&admin, &token_id, &10, &Variant::Classic, &4, &prizes);
+    client.join_tourn(&tourn, &u1);
+    assert_eq!(
+        client.try_drop_tourn(&tourn),
+        Err(Ok(Error::LimitNotReached))
+    );
+
+    e.ledger().with_mut(|li| li.timestamp = 1000 + 2 * 86400);
+    client.drop_tourn(&tourn);
+    assert_eq!(client.get_tourn(&tourn).phase, GamePhase::Cancelled);
+    assert_eq!(token.balance(&u1), 98);
+}
+
+#[test]
+fn test_tourn_budget() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+
+    // a running season has every player recorded on its board too
+    client.new_season(
+        &token_id,
+        &TimeStamp(0),
+        &TimeStamp(1000),
+        &vec![&e, 10_000],
+    );
+
+    let tourn = client.new_tourn(
+        &admin,
+        &token_id,
+        &10,
+        &Variant::Classic,
+        &8,
+        &vec![&e, 7000_u32, 2000, 1000],
+    );
+    for _ in 0..8 {
+        let user = Address::random(&e);
+        token.mint(&admin, &user, &10);
+        token.incr_allow(&user, &contract, &10);
+        // the last one pairs the whole first round
+        e.budget().reset();
+        client.join_tourn(&tourn, &user);
+        assert!(e.budget().cpu_instruction_cost() < CPU_LIMIT);
+    }
+
+    // the better seed wins every match, played from the first round up to the final. Every other
+    // match pairs the next one, and the final pays the prizes
+    let matches = client.get_tourn(&tourn).matches;
+    for i in (0..matches.len()).rev() {
+        let game = matches.get_unchecked(i).unwrap();
+        let allow = client.get_game(&game).allow;
+        let (one, two) = (
+            allow.get_unchecked(0).unwrap(),
+            allow.get_unchecked(1).unwrap(),
+        );
+        client.make_move(
+            &game,
+            &one,
+            &commit(&e, &contract, &game, &one, Move::Rock, "s0"),
+            &0,
+        );
+        client.make_move(
+            &game,
+            &two,
+            &commit(&e, &contract, &game, &two, Move::Scissors, "s1"),
+            &0,
+        );
+        client.reveal(
+            &game,
+            &Player::One,
+            &Move::Rock,
+            &Bytes::from_slice(&e, b"s0"),
+        );
+        client.reveal(
+            &game,
+            &Player::Two,
+            &Move::Scissors,
+            &Bytes::from_slice(&e, b"s1"),
+        );
+        e.budget().reset();
+        client.evaluate(&game);
+        assert!(e.budget().cpu_instruction_cost() < CPU_LIMIT);
+    }
+    assert_eq!(client.get_tourn(&tourn).phase, GamePhase::Settled);
+}
+
+#[test]
+fn test_stats() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    assert_eq!(client.get_stats(&u1), Stats::default());
+
+    // u1 wins, then both draw
+    for (one, two) in [(Move::Paper, Move::Rock), (Move::Rock, Move::Rock)] {
+        let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+        client.make_move(
+            &game,
+            &u1,
+            &commit(&e, &contract, &game, &u1, one, "s0"),
+            &10,
+        );
+        client.make_move(
+            &game,
+            &u2,
+            &commit(&e, &contract, &game, &u2, two, "s1"),
+            &10,
+        );
+        client.reveal(&game, &Player::One, &one, &Bytes::from_slice(&e, b"s0"));
+        client.reveal(&game, &Player::Two, &two, &Bytes::from_slice(&e, b"s1"));
+        client.evaluate(&game);
+    }
+
+    // u2 doesn't reveal in time
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s2"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s3"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    e.ledger().with_mut(|li| li.timestamp = 3601);
+    client.cancel(&game);
+
+    assert_eq!(
+        client.get_stats(&u1),
+        Stats {
+            wins: 2,
+            losses: 0,
+            draws: 1,
+            forfeits: 0,
+            wagered: 30,
+            net_profit: 20,
+        }
+    );
+    assert_eq!(
+        client.get_stats(&u2),
+        Stats {
+            wins: 0,
+            losses: 1,
+            draws: 1,
+            forfeits: 1,
+            wagered: 30,
+            net_profit: -20,
+        }
+    );
+    assert_eq!(token.balance(&u1), 120);
+    assert_eq!(token.balance(&u2), 80);
+}
+
+#[test]
+fn test_rating() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    assert_eq!(
+        client.try_initialize(
+            &token_id,
+            &1,
+            &1000,
+            &TimeStamp(3600),
+            &TimeStamp(86400),
+            &None,
+            &0,
+            &admin,
+            &0,
+            &admin
+        ),
+        Err(Ok(Error::InvalidKFactor))
+    );
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    assert_eq!(client.get_rating(&u1), 1500);
+
+    // u1 wins, then both draw and the favourite loses a point
+    for (one, two, rating) in [
+        (Move::Paper, Move::Rock, 1516),
+        (Move::Rock, Move::Rock, 1515),
+    ] {
+        let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+        client.make_move(
+            &game,
+            &u1,
+            &commit(&e, &contract, &game, &u1, one, "s0"),
+            &10,
+        );
+        client.make_move(
+            &game,
+            &u2,
+            &commit(&e, &contract, &game, &u2, two, "s1"),
+            &10,
+        );
+        client.reveal(&game, &Player::One, &one, &Bytes::from_slice(&e, b"s0"));
+        client.reveal(&game, &Player::Two, &two, &Bytes::from_slice(&e, b"s1"));
+        client.evaluate(&game);
+
+        assert_eq!(client.get_rating(&u1), rating);
+        assert_eq!(client.get_rating(&u2), 3000 - rating);
+    }
+
+    // u2 doesn't reveal in time
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u1,
+        &commit(&e, &contract, &game, &u1, Move::Rock, "s2"),
+        &10,
+    );
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s3"),
+        &10,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    e.ledger().with_mut(|li| li.timestamp = 3601);
+    client.cancel(&game);
+
+    assert_eq!(client.get_rating(&u1), 1529);
+    assert_eq!(client.get_rating(&u2), 1471);
+}
+
+#[test]
+fn test_season() {
+    let (e, client, token, contract, admin) = setup();
+    e.budget().reset();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+
+    for user in [&admin, &u1, &u2, &u3] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    let prizes = vec![&e, 7000, 3000];
+    assert_eq!(
+        client.try_new_season(&token_id, &TimeStamp(100), &TimeStamp(100), &prizes),
+        Err(Ok(Error::InvalidSeason))
+    );
+    assert_eq!(
+        client.try_new_season(
+            &token_id,
+            &TimeStamp(100),
+            &TimeStamp(1000),
+            &vec![&e, 7000]
+        ),
+        Err(Ok(Error::InvalidPrizes))
+    );
+    assert_eq!(
+        client.try_new_season(
+            &token_id,
+            &TimeStamp(100),
+            &TimeStamp(1000),
+            &vec![&e, u32::MAX, 10_001]
+        ),
+        Err(Ok(Error::InvalidPrizes))
+    );
+
+    let season = client.new_season(&token_id, &TimeStamp(100), &TimeStamp(1000), &prizes);
+    assert_eq!(
+        client.try_new_season(&token_id, &TimeStamp(900), &TimeStamp(2000), &prizes),
+        Err(Ok(Error::InvalidSeason))
+    );
+    client.fund_pool(&season, &100);
+    assert_eq!(client.get_season(&season).pool, 100);
+
+    // the first game is played before the season starts and doesn't count
+    for (time, one, two, bet) in [(0, &u1, &u2, 10), (100, &u1, &u2, 10), (200, &u3, &u2, 20)] {
+        e.ledger().with_mut(|li| li.timestamp = time);
+        let game = client.new_game(one, &None, &token_id, &bet, &Variant::Classic, &1, &0);
+        client.make_move(
+            &game,
+            one,
+            &commit(&e, &contract, &game, one, Move::Paper, "s0"),
+            &bet,
+        );
+        client.make_move(
+            &game,
+            two,
+            &commit(&e, &contract, &game, two, Move::Rock, "s1"),
+            &bet,
+        );
+        client.reveal(
+            &game,
+            &Player::One,
+            &Move::Paper,
+            &Bytes::from_slice(&e, b"s0"),
+        );
+        client.reveal(
+            &game,
+            &Player::Two,
+            &Move::Rock,
+            &Bytes::from_slice(&e, b"s1"),
+        );
+        client.evaluate(&game);
+    }
+
+    assert_eq!(
+        client.get_season(&season).board,
+        vec![
+            &e,
+            Standing {
+                player: u3.clone(),
+                net: 20
+            },
+            Standing {
+                player: u1.clone(),
+                net: 10
+            },
+        ]
+    );
+
+    // losing its winnings moves u3 down without taking it off the board, and u2 isn't ahead yet
+    e.ledger().with_mut(|li| li.timestamp = 300);
+    let game = client.new_game(&u2, &None, &token_id, &20, &Variant::Classic, &1, &0);
+    client.make_move(
+        &game,
+        &u2,
+        &commit(&e, &contract, &game, &u2, Move::Paper, "s2"),
+        &20,
+    );
+    client.make_move(
+        &game,
+        &u3,
+        &commit(&e, &contract, &game, &u3, Move::Rock, "s3"),
+        &20,
+    );
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s2"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    client.evaluate(&game);
+    assert_eq!(
+        client.get_season(&season).board,
+        vec![
+            &e,
+            Standing {
+                player: u1.clone(),
+                net: 10
+            },
+            Standing {
+                player: u3.clone(),
+                net: 0
+            },
+        ]
+    );
+
+    assert_eq!(
+        client.try_end_season(&season),
+        Err(Ok(Error::LimitNotReached))
+    );
+
+    // u3 isn't ahead anymore, its share goes back to the admin
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+    client.end_season(&season);
+    assert_eq!(client.get_season(&season).phase, GamePhase::Settled);
+    assert_eq!(client.try_end_season(&season), Err(Ok(Error::WrongPhase)));
+
+    assert_eq!(token.balance(&u3), 100);
+    assert_eq!(token.balance(&u1), 190);
+    assert_eq!(token.balance(&u2), 80);
+    assert_eq!(token.balance(&admin), 30);
+    assert_eq!(token.balance(&contract), 0);
+}
+
+#[test]
+fn test_history() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+
+    for user in [&u1, &u2] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    e.ledger().with_mut(|li| li.timestamp = 1000);
+    let game = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    let c1 = commit(&e, &contract, &game, &u1, Move::Paper, "s0");
+    let c2 = commit(&e, &contract, &game, &u2, Move::Rock, "s1");
+    client.make_move(&game, &u1, &c1, &10);
+    client.make_move(&game, &u2, &c2, &10);
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Paper,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s1"),
+    );
+    e.ledger().with_mut(|li| li.timestamp = 2000);
+    client.evaluate(&game);
+
+    // nobody joins u1's second game
+    let open = client.new_game(&u1, &None, &token_id, &10, &Variant::Classic, &1, &0);
+    client.make_move(
+        &open,
+        &u1,
+        &commit(&e, &contract, &open, &u1, Move::Rock, "s2"),
+        &10,
+    );
+    e.ledger().with_mut(|li| li.timestamp = 2000 + 86400);
+    client.cancel(&open);
+
+    let history = client.history(&u1, &0, &10);
+    assert_eq!(history.len(), 2);
+
+    let settled = history.get_unchecked(0).unwrap();
+    assert_eq!(settled.game, game);
+    assert_eq!(settled.phase, GamePhase::Settled);
+    assert_eq!(settled.bet, 10);
+    assert_eq!(settled.players.len(), 2);
+    let one = settled.players.get_unchecked(0).unwrap();
+    let two = settled.players.get_unchecked(1).unwrap();
+    assert_eq!(
+        (one.id, one.user_move, one.move_pre),
+        (u1.clone(), c1, Move::Paper)
+    );
+    assert_eq!(
+        (two.id, two.user_move, two.move_pre),
+        (u2.clone(), c2, Move::Rock)
+    );
+    assert_eq!((settled.paid_one, settled.paid_two), (20, 0));
+    assert_eq!(settled.bet_start, TimeStamp(1000));
+    assert_eq!(settled.ended, TimeStamp(2000));
+
+    let cancelled = history.get_unchecked(1).unwrap();
+    assert_eq!(cancelled.game, open);
+    assert_eq!(cancelled.phase, GamePhase::Cancelled);
+    assert_eq!(cancelled.players.len(), 1);
+    assert_eq!((cancelled.paid_one, cancelled.paid_two), (10, 0));
+
+    // pages
+    let page = client.history(&u1, &1, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get_unchecked(0).unwrap().game, open);
+    assert_eq!(client.history(&u1, &2, &10).len(), 0);
+    assert_eq!(client.history(&u2, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_queue() {
+    let (e, client, token, contract, admin) = setup();
+    let token_id = token.contract_id.clone();
+    let u1 = Address::random(&e);
+    let u2 = Address::random(&e);
+    let u3 = Address::random(&e);
+
+    for user in [&u1, &u2, &u3] {
+        token.mint(&admin, user, &100);
+        token.incr_allow(user, &contract, &100);
+    }
+
+    let c1 = commit(&e, &contract, &QUEUE_GAME, &u1, Move::Rock, "s0");
+    assert_eq!(client.join_queue(&u1, &token_id, &10, &c1), None);
+    assert_eq!(
+        client.try_join_queue(
+            &u1,
+            &token_id,
+            &10,
+            &commit(&e, &contract, &QUEUE_GAME, &u1, Move::Paper, "s1")
+        ),
+        Err(Ok(Error::AlreadyJoined))
+    );
+
+    // a different stake is a different queue
+    let c2 = commit(&e, &contract, &QUEUE_GAME, &u2, Move::Rock, "s2");
+    assert_eq!(client.join_queue(&u2, &token_id, &20, &c2), None);
+    assert_eq!(client.get_queue(&token_id, &10).unwrap().user, u1);
+    assert_eq!(token.balance(&contract), 30);
+
+    let c3 = commit(&e, &contract, &QUEUE_GAME, &u3, Move::Scissors, "s3");
+    let game = client.join_queue(&u3, &token_id, &10, &c3).unwrap();
+    assert!(client.get_queue(&token_id, &10).is_none());
+
+    let view = client.get_game(&game);
+    assert_eq!(view.phase, GamePhase::Committed);
+    assert_eq!(view.bet, 10);
+    assert_eq!(view.players.get_unchecked(0).unwrap().id, u1);
+    assert_eq!(view.players.get_unchecked(1).unwrap().id, u3);
+
+    client.reveal(
+        &game,
+        &Player::One,
+        &Move::Rock,
+        &Bytes::from_slice(&e, b"s0"),
+    );
+    client.reveal(
+        &game,
+        &Player::Two,
+        &Move::Scissors,
+        &Bytes::from_slice(&e, b"s3"),
+    );
+    assert_eq!(client.evaluate(&game), GameResult::Winner(Player::One));
+    assert_eq!(token.balance(&u1), 110);
+    assert_eq!(token.balance(&u3), 90);
+
+    // u2 gives up waiting
+    client.drop_queue(&u2, &token_id, &20);
+    assert_eq!(
+        client.try_drop_queue(&u2, &token_id, &20),
+        Err(Ok(Error::InvalidOp))
+    );
+    assert_eq!(token.balance(&u2), 100);
+    assert_eq!(token.balance(&contract), 0);
+}
+
 /*
 #[test]
 fn test_draw() {
@@ -0,0 +1,241 @@
+use crate::{
+    events, get_allow, get_fee_balance, next_game_id, put_allow, put_bet_start, put_creator,
+    put_fee_balance, put_game_bet, put_game_token, put_phase, put_replays, put_rounds, put_ruleset,
+    put_variant, send_profit, split_pot, take_fee, DataKey, Error, GameId, GamePhase, TimeStamp,
+    Variant, MAX_REPLAYS,
+};
+
+use soroban_sdk::{contracttype, panic_with_error, vec, Address, BytesN, Env, Vec};
+
+// upper bound on the players of a tournament
+pub(crate) const MAX_PLAYERS: u32 = 8;
+
+#[contracttype]
+#[derive(Clone)]
+/// A single-elimination tournament, returned by `get_tourn`
+pub struct Tournament {
+    pub creator: Address,
+    pub token: BytesN<32>,
+    /// entry fee every player pays when registering
+    pub fee: i128,
+    /// variant every match is played with
+    pub variant: Variant,
+    /// number of players, a power of two
+    pub size: u32,
+    /// shares of the pot in basis points for the champion, the runner-up and the two semifinal
+    /// losers, who split theirs
+    pub prizes: Vec<u32>,
+    /// `Open` while registering, `Committed` while the bracket is played, `Settled` once the
+    /// prizes are paid and `Cancelled` if registration didn't fill up in time
+    pub phase: GamePhase,
+    /// time of the latest registration, or of the tournament's creation if nobody registered yet
+    pub bet_start: TimeStamp,
+    /// registered players, by seed
+    pub players: Vec<Address>,
+    /// games of the whole bracket, the final first. The winners of games `2i + 1` and `2i + 2`
+    /// meet in game `i`, which can only be played once both are known
+    pub matches: Vec<GameId>,
+    /// the champion, the runner-up then the semifinal losers once the tournament is over
+    pub ranking: Vec<Address>,
+}
+
+pub(crate) fn valid_prizes(prizes: &Vec<u32>) -> bool {
+    if prizes.is_empty() || prizes.len() > 3 {
+        return false;
+    }
+
+    // capping every share also keeps the total from wrapping around
+    let mut total = 0;
+    for bps in prizes.iter() {
+        let bps = bps.unwrap();
+        if bps > 10_000 {
+            return false;
+        }
+        total += bps;
+    }
+    total == 10_000
+}
+
+pub(crate) fn put_tournament(e: &Env, tourn_id: GameId, tourn: &Tournament) {
+    let key = DataKey::Tourn(tourn_id);
+    e.storage().set(&key, tourn);
+}
+
+pub(crate) fn get_tournament(e: &Env, tourn_id: GameId) -> Result<Tournament, Error> {
+    let key = DataKey::Tourn(tourn_id);
+    match e.storage().get(&key) {
+        Some(tourn) => Ok(tourn.unwrap()),
+        None => Err(Error::NoSuchGame),
+    }
+}
+
+fn get_match_tournament(e: &Env, game: GameId) -> Option<GameId> {
+    let key = DataKey::Match(game);
+    e.storage().get(&key).map(|tourn_id| tourn_id.unwrap())
+}
+
+pub(crate) fn is_match(e: &Env, game: GameId) -> bool {
+    get_match_tournament(e, game).is_some()
+}
+
+fn get_match_winner(e: &Env, game: GameId) -> Option<Address> {
+    let key = DataKey::Winner(game);
+    e.storage().get(&key).map(|winner| winner.unwrap())
+}
+
+// seeds (counted from 0) in first round bracket order, every two of them play each other. The
+// best seeds can only meet in the last rounds
+fn bracket_order(e: &Env, size: u32) -> Vec<u32> {
+    let mut order = vec![e, 0];
+    let mut n = 1;
+    while n < size {
+        n *= 2;
+        let mut next = Vec::new(e);
+        for seed in order.iter() {
+            let seed = seed.unwrap();
+            next.push_back(seed);
+            next.push_back(n - 1 - seed);
+        }
+        order = next;
+    }
+    order
+}
+
+// a tournament match is a regular game restricted to its two players through the allow list,
+// the better seed first. There's nothing at stake in it, and draws are replayed
+fn new_match(e: &Env, tourn_id: GameId, tourn: &Tournament, game: GameId, a: Address, b: Address) {
+    let a_seed = tourn.players.first_index_of(&a);
+    let pair = if a_seed < tourn.players.first_index_of(&b) {
+        vec![e, a, b]
+    } else {
+        vec![e, b, a]
+    };
+
+    put_phase(e, game, GamePhase::Open);
+    put_creator(e, game, tourn.creator.clone());
+    put_allow(e, game, pair);
+    put_game_token(e, game, tourn.token.clone());
+    put_game_bet(e, game, 0);
+    put_variant(e, game, tourn.variant);
+    put_ruleset(e, game, tourn.variant.ruleset(e));
+    put_rounds(e, game, 1);
+    put_replays(e, game, MAX_REPLAYS);
+    // starts the join limit, so that a match nobody shows up to can be cancelled
+    put_bet_start(e, game, TimeStamp::current(e));
+    e.storage().set(&DataKey::Match(game), &tourn_id);
+    events::created(
+        e,
+        game,
+        tourn.creator.clone(),
+        tourn.token.clone(),
+        0,
+        tourn.variant,
+        1,
+    );
+}
+
+// numbers every game of the bracket and pairs the first round once registration is full. The
+// later games are only created as they're paired, which keeps any single call from creating
+// more than the first round
+pub(crate) fn start(e: &Env, tourn_id: GameId, tourn: &mut Tournament) {
+    let mut matches = Vec::new(e);
+    for _ in 1..tourn.size {
+        matches.push_back(next_game_id(e));
+    }
+    tourn.matches = matches;
+    tourn.phase = GamePhase::Committed;
+
+    // the first round is the last half of the bracket
+    let order = bracket_order(e, tourn.size);
+    let first = tourn.size / 2 - 1;
+    let mut paired = Vec::new(e);
+    for i in 0..tourn.size / 2 {
+        let game = tourn.matches.get_unchecked(first + i).unwrap();
+        let a = tourn
+            .players
+            .get_unchecked(order.get_unchecked(2 * i).unwrap())
+            .unwrap();
+        let b = tourn
+            .players
+            .get_unchecked(order.get_unchecked(2 * i + 1).unwrap())
+            .unwrap();
+        new_match(e, tourn_id, tourn, game, a, b);
+        paired.push_back(game);
+    }
+    events::tourn_round(e, tourn_id, paired);
+}
+
+fn pay_prizes(e: &Env, tourn: &Tournament) {
+    let pot = take_fee(e, &tourn.token, tourn.fee * tourn.size as i128);
+    let mut paid = 0;
+    for (place, bps) in tourn.prizes.iter().enumerate() {
+        let amount = pot * bps.unwrap() as i128 / 10_000;
+        let to = if place < 2 {
+            vec![e, tourn.ranking.get_unchecked(place as u32).unwrap()]
+        } else {
+            tourn.ranking.slice(2..)
+        };
+        split_pot(e, &tourn.token, amount, &to);
+        paid += amount;
+    }
+
+    if pot > paid {
+        put_fee_balance(
+            e,
+            tourn.token.clone(),
+            get_fee_balance(e, tourn.token.clone()) + pot - paid,
+        );
+    }
+}
+
+// called whenever a game is over, advances the winner if it's a tournament match. A drawn or
+// abandoned match goes to the better seed. The next game is paired as soon as the other game
+// feeding it is over too, and the prizes are paid after the final
+pub(crate) fn match_over(e: &Env, game: GameId, winner: Option<Address>) {
+    let tourn_id = match get_match_tournament(e, game) {
+        Some(tourn_id) => tourn_id,
+        None => return,
+    };
+    let mut tourn = get_tournament(e, tourn_id).unwrap_or_else(|err| panic_with_error!(e, err));
+
+    let winner = winner.unwrap_or_else(|| get_allow(e, game).get_unchecked(0).unwrap());
+    e.storage().set(&DataKey::Winner(game), &winner);
+
+    let index = tourn.matches.first_index_of(game).unwrap();
+    if index > 0 {
+        let sibling = if index % 2 == 1 { index + 1 } else { index - 1 };
+        if let Some(other) = get_match_winner(e, tourn.matches.get_unchecked(sibling).unwrap()) {
+            let next = tourn.matches.get_unchecked((index - 1) / 2).unwrap();
+            new_match(e, tourn_id, &tourn, next, winner, other);
+            events::tourn_round(e, tourn_id, vec![e, next]);
+        }
+        return;
+    }
+
+    // the semifinals are the games feeding the final
+    let mut ranking = vec![e, winner.clone()];
+    for m in 0..3 {
+        for player in get_allow(e, tourn.matches.get_unchecked(m).unwrap()).iter() {
+            let player = player.unwrap();
+            if player != winner && !ranking.contains(&player) {
+                ranking.push_back(player);
+            }
+        }
+    }
+    tourn.ranking = ranking;
+
+    pay_prizes(e, &tourn);
+    tourn.phase = GamePhase::Settled;
+    events::tourn_won(e, tourn_id, tourn.ranking.clone());
+    put_tournament(e, tourn_id, &tourn);
+}
+
+// refunds the registered players their entry fee minus the house fee
+pub(crate) fn refund(e: &Env, tourn_id: GameId, tourn: &Tournament) {
+    for player in tourn.players.iter() {
+        let player = player.unwrap();
+        let payout = take_fee(e, &tourn.token, tourn.fee);
+        send_profit(e, &tourn.token, player.clone(), payout);
+        events::cancelled(e, tourn_id, player, payout);
+    }
+}